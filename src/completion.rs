@@ -0,0 +1,379 @@
+use serde::Deserialize;
+use zed_extension_api::{self as zed};
+
+/// Controls how much detail `label_for_completion` keeps in a completion's
+/// display label. `detailed` (the default) keeps whatever jdtls sends;
+/// `compact` drops the leading return type and any `pkg.Sub.` qualifiers,
+/// keeping only simple names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionLabelStyle {
+    Compact,
+    #[default]
+    Detailed,
+}
+
+/// Builds the `CodeLabel` a completion should render with, honoring
+/// `style`. The main label is treated as a single code span; we're only
+/// reshaping jdtls' text, not adding syntax highlighting jdtls didn't
+/// already bake into the label itself. A `throws` clause, when present, is
+/// appended as a separate literal span rather than folded into `code` —
+/// `throws ExceptionType` isn't a valid continuation of the pseudo-code
+/// `code` gets tree-sitter-parsed as, so it can't ride along inside the
+/// same code-range span. Likewise, a [`keyword_prefix`] (e.g. `module `) is
+/// a leading literal span rather than part of `code`, so it's never
+/// included in filtering or affected by `compact_label`'s qualifier
+/// stripping.
+pub fn build_completion_label(completion: &zed::lsp::Completion, style: CompletionLabelStyle) -> zed::CodeLabel {
+    let (raw, throws) = method_label_and_throws(completion);
+    let code = match style {
+        CompletionLabelStyle::Detailed => raw,
+        CompletionLabelStyle::Compact => compact_label(&raw),
+    };
+    let len = code.len();
+    let mut spans = Vec::new();
+    if let Some(prefix) = keyword_prefix(completion.kind) {
+        spans.push(zed::CodeLabelSpan::literal(prefix, Some("keyword".to_string())));
+    }
+    spans.push(zed::CodeLabelSpan::code_range(0..len));
+    if let Some(throws) = throws {
+        let throws = match style {
+            CompletionLabelStyle::Detailed => throws,
+            CompletionLabelStyle::Compact => strip_qualifiers(&throws),
+        };
+        spans.push(zed::CodeLabelSpan::literal(format!(" throws {throws}"), None));
+    }
+    zed::CodeLabel {
+        spans,
+        filter_range: (0..len).into(),
+        code,
+    }
+}
+
+/// A leading keyword to render ahead of a completion's label, for kinds
+/// that name a Java declaration jdtls doesn't already spell out as a
+/// keyword-led label (unlike, say, `class Foo`, which jdtls's `label` sends
+/// pre-formatted).
+///
+/// `Module` completions (`java.base`, `java.sql`, ...) are the only case
+/// today: jdtls sends just the dotted module name, so `module ` is
+/// prepended to match how jdtls itself formats package/class declarations.
+/// There's no `Package` variant in this API's `CompletionKind` (LSP itself
+/// doesn't define one), so package completions fall through to the
+/// catch-all `Text`/`Reference`/etc. handling below, same as before.
+fn keyword_prefix(kind: Option<zed::lsp::CompletionKind>) -> Option<&'static str> {
+    match kind {
+        Some(zed::lsp::CompletionKind::Module) => Some("module "),
+        _ => None,
+    }
+}
+
+/// For a `Method` completion, jdtls sends the bare method name in `label`
+/// and the signature in `detail`, shaped like `(params) : ReturnType`. For
+/// inherited methods, `detail` sometimes instead reads
+/// `(params) : ReturnType (from DeclaringClass)`. Both shapes split cleanly
+/// on the same `" : "` separator — the declaring-type suffix, when present,
+/// rides along inside the return-type half and needs no special casing to
+/// render correctly.
+///
+/// Methods that declare checked exceptions have a `" throws "` clause
+/// between the params and the `" : "` separator, e.g.
+/// `(params) throws IOException : ReturnType`. That clause is pulled out
+/// and returned separately rather than folded into the label string — see
+/// [`build_completion_label`] for why.
+fn method_label_and_throws(completion: &zed::lsp::Completion) -> (String, Option<String>) {
+    match (completion.kind, completion.detail.as_deref()) {
+        (Some(zed::lsp::CompletionKind::Method), Some(detail)) => match split_outside_generics(detail, " : ") {
+            Some((params, return_type)) => {
+                let (params, throws) = split_throws_clause(params);
+                (format!("{}{params} : {return_type}", completion.label), throws)
+            }
+            None => {
+                let (detail, throws) = split_throws_clause(detail);
+                (format!("{}{detail}", completion.label), throws)
+            }
+        },
+        _ => (completion.label.clone(), None),
+    }
+}
+
+/// Splits a `"(params) throws Ex1, Ex2"` detail fragment into
+/// `("(params)", Some("Ex1, Ex2"))`, or returns `text` unchanged with
+/// `None` when there's no `" throws "` clause.
+fn split_throws_clause(text: &str) -> (&str, Option<String>) {
+    match text.find(" throws ") {
+        Some(index) => (&text[..index], Some(text[index + " throws ".len()..].trim().to_string())),
+        None => (text, None),
+    }
+}
+
+/// Like `str::split_once`, but ignores matches of `separator` nested inside
+/// `<...>`. Generic types like `Map<K, V>` can themselves contain `" : "`
+/// as part of a nested bound or inherited-method suffix, e.g.
+/// `(params) : Map<String, List<Integer>>`; splitting at the first
+/// occurrence regardless of nesting would cut the return type in half.
+fn split_outside_generics<'a>(text: &'a str, separator: &str) -> Option<(&'a str, &'a str)> {
+    let mut depth = 0i32;
+    for (i, ch) in text.char_indices() {
+        match ch {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 && text[i..].starts_with(separator) {
+            return Some((&text[..i], &text[i + separator.len()..]));
+        }
+    }
+    None
+}
+
+/// jdtls labels are roughly shaped like `returnType name(paramType param, ...)`
+/// for methods, or just `name(paramType param, ...)` for constructors, or a
+/// bare `name` for fields/keywords. Compacting means dropping the leading
+/// return type (when there is one) and reducing every qualified type name
+/// to its simple name.
+fn compact_label(label: &str) -> String {
+    strip_qualifiers(strip_leading_return_type(label))
+}
+
+/// Drops a leading `returnType ` token, but only when the label's first
+/// space comes before its first `(` — a constructor like `Foo(int x)` has
+/// its first space *inside* the parameter list, so `find(' ') < find('(')`
+/// being false correctly leaves it untouched.
+fn strip_leading_return_type(label: &str) -> &str {
+    match (label.find(' '), label.find('(')) {
+        (Some(space), Some(paren)) if space < paren => &label[space + 1..],
+        (Some(space), None) => &label[space + 1..],
+        _ => label,
+    }
+}
+
+/// Reduces every dotted run of identifiers (e.g. `java.util.List`) to its
+/// last segment (`List`), leaving everything else untouched.
+fn strip_qualifiers(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut run = String::new();
+
+    for ch in text.chars() {
+        if ch.is_alphanumeric() || ch == '_' || ch == '.' {
+            run.push(ch);
+        } else {
+            result.push_str(last_segment(&run));
+            run.clear();
+            result.push(ch);
+        }
+    }
+    result.push_str(last_segment(&run));
+    result
+}
+
+fn last_segment(run: &str) -> &str {
+    run.rsplit('.').next().unwrap_or(run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method_completion(name: &str, detail: &str) -> zed::lsp::Completion {
+        zed::lsp::Completion {
+            label: name.to_string(),
+            detail: Some(detail.to_string()),
+            kind: Some(zed::lsp::CompletionKind::Method),
+            insert_text_format: None,
+        }
+    }
+
+    fn field_completion(name: &str) -> zed::lsp::Completion {
+        zed::lsp::Completion {
+            label: name.to_string(),
+            detail: None,
+            kind: Some(zed::lsp::CompletionKind::Field),
+            insert_text_format: None,
+        }
+    }
+
+    fn module_completion(name: &str) -> zed::lsp::Completion {
+        zed::lsp::Completion {
+            label: name.to_string(),
+            detail: None,
+            kind: Some(zed::lsp::CompletionKind::Module),
+            insert_text_format: Some(zed::lsp::InsertTextFormat::PlainText),
+        }
+    }
+
+    #[test]
+    fn detailed_style_keeps_label_verbatim_for_non_methods() {
+        assert_eq!(
+            build_completion_label(&field_completion("name"), CompletionLabelStyle::Detailed).code,
+            "name"
+        );
+    }
+
+    #[test]
+    fn method_label_combines_name_and_signature() {
+        let completion = method_completion("getName", "(int index) : java.lang.String");
+        assert_eq!(
+            method_label_and_throws(&completion).0,
+            "getName(int index) : java.lang.String"
+        );
+    }
+
+    #[test]
+    fn method_label_preserves_declaring_type_for_inherited_methods() {
+        let completion = method_completion("toString", "() : String (from AbstractPerson)");
+        assert_eq!(
+            method_label_and_throws(&completion).0,
+            "toString() : String (from AbstractPerson)"
+        );
+    }
+
+    #[test]
+    fn compact_style_simplifies_declaring_type_qualifiers_too() {
+        let completion = method_completion("compareTo", "(java.lang.Object o) : int (from java.lang.Comparable)");
+        assert_eq!(
+            build_completion_label(&completion, CompletionLabelStyle::Compact).code,
+            "compareTo(Object o) : int (from Comparable)"
+        );
+    }
+
+    #[test]
+    fn compact_style_drops_return_type_and_qualifiers() {
+        let completion = method_completion("getName", "(int index) : java.lang.String");
+        assert_eq!(
+            build_completion_label(&completion, CompletionLabelStyle::Compact).code,
+            "getName(int index) : String"
+        );
+    }
+
+    #[test]
+    fn compact_style_leaves_constructors_untouched_by_return_type_stripping() {
+        assert_eq!(compact_label("Foo(int x)"), "Foo(int x)");
+    }
+
+    #[test]
+    fn compact_style_simplifies_qualified_parameter_types() {
+        assert_eq!(
+            compact_label("void accept(java.util.function.Consumer<java.lang.String> consumer)"),
+            "accept(Consumer<String> consumer)"
+        );
+    }
+
+    #[test]
+    fn compact_style_leaves_bare_field_names_untouched() {
+        assert_eq!(compact_label("name"), "name");
+    }
+
+    #[test]
+    fn method_label_splits_at_the_top_level_separator_with_generic_params_and_return_type() {
+        let completion = method_completion(
+            "merge",
+            "(Map<String, List<Integer>> other) : Map<String, List<Integer>>",
+        );
+        assert_eq!(
+            method_label_and_throws(&completion).0,
+            "merge(Map<String, List<Integer>> other) : Map<String, List<Integer>>"
+        );
+    }
+
+    #[test]
+    fn method_label_handles_nested_generics_with_declaring_type_suffix() {
+        let completion = method_completion(
+            "entrySet",
+            "() : Set<Map.Entry<K,V>> (from AbstractMap<K,V>)",
+        );
+        assert_eq!(
+            method_label_and_throws(&completion).0,
+            "entrySet() : Set<Map.Entry<K,V>> (from AbstractMap<K,V>)"
+        );
+    }
+
+    #[test]
+    fn compact_style_simplifies_nested_generic_return_types() {
+        let completion = method_completion(
+            "asMap",
+            "() : java.util.Map<java.lang.String, java.util.List<java.lang.Integer>>",
+        );
+        assert_eq!(
+            build_completion_label(&completion, CompletionLabelStyle::Compact).code,
+            "asMap() : Map<String, List<Integer>>"
+        );
+    }
+
+    #[test]
+    fn method_without_throws_has_a_single_code_range_span() {
+        let completion = method_completion("getName", "(int index) : java.lang.String");
+        let label = build_completion_label(&completion, CompletionLabelStyle::Detailed);
+        assert_eq!(label.spans.len(), 1);
+    }
+
+    #[test]
+    fn method_with_throws_renders_it_as_a_trailing_literal_span() {
+        let completion = method_completion("readLine", "() throws IOException : String");
+        let label = build_completion_label(&completion, CompletionLabelStyle::Detailed);
+
+        assert_eq!(label.code, "readLine() : String");
+        assert_eq!(label.spans.len(), 2);
+        match &label.spans[1] {
+            zed::CodeLabelSpan::Literal(literal) => assert_eq!(literal.text, " throws IOException"),
+            other => panic!("expected a literal span, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn method_with_multiple_throws_types_keeps_them_together_in_one_span() {
+        let completion = method_completion(
+            "copy",
+            "(Path src, Path dst) throws IOException, SecurityException : void",
+        );
+        let label = build_completion_label(&completion, CompletionLabelStyle::Detailed);
+
+        assert_eq!(label.code, "copy(Path src, Path dst) : void");
+        match &label.spans[1] {
+            zed::CodeLabelSpan::Literal(literal) => {
+                assert_eq!(literal.text, " throws IOException, SecurityException")
+            }
+            other => panic!("expected a literal span, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn compact_style_simplifies_qualified_throws_types_too() {
+        let completion = method_completion("readLine", "() throws java.io.IOException : String");
+        let label = build_completion_label(&completion, CompletionLabelStyle::Compact);
+
+        match &label.spans[1] {
+            zed::CodeLabelSpan::Literal(literal) => assert_eq!(literal.text, " throws IOException"),
+            other => panic!("expected a literal span, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn module_completions_get_a_leading_module_keyword_span() {
+        let label = build_completion_label(&module_completion("java.sql"), CompletionLabelStyle::Detailed);
+
+        assert_eq!(label.code, "java.sql");
+        assert_eq!(label.spans.len(), 2);
+        match &label.spans[0] {
+            zed::CodeLabelSpan::Literal(literal) => {
+                assert_eq!(literal.text, "module ");
+                assert_eq!(literal.highlight_name.as_deref(), Some("keyword"));
+            }
+            other => panic!("expected a literal span, got {other:?}"),
+        }
+        assert!(matches!(label.spans[1], zed::CodeLabelSpan::CodeRange(_)));
+    }
+
+    #[test]
+    fn module_keyword_prefix_is_not_part_of_the_filter_range() {
+        let label = build_completion_label(&module_completion("java.base"), CompletionLabelStyle::Detailed);
+        assert_eq!(label.filter_range.start, 0);
+        assert_eq!(label.filter_range.end, label.code.len() as u32);
+    }
+
+    #[test]
+    fn non_module_completions_have_no_keyword_prefix() {
+        let label = build_completion_label(&field_completion("name"), CompletionLabelStyle::Detailed);
+        assert_eq!(label.spans.len(), 1);
+    }
+}
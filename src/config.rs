@@ -0,0 +1,876 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use zed_extension_api::{self as zed, Result};
+
+use crate::completion::CompletionLabelStyle;
+use crate::debugger::DebugSettings;
+use crate::jdtls::CheckUpdates;
+use crate::lombok::LombokSettings;
+use crate::runtime::RuntimeConfig;
+use crate::util::{check_pointer_shape, deep_merge, json_object_mut, PointerCheck};
+
+/// `java.compile.nullAnalysis.mode`. `Automatic` runs null analysis on every
+/// build without requiring `@NonNullByDefault`-style project annotations;
+/// `Interactive` only analyzes files that already opt in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NullAnalysisMode {
+    #[default]
+    Disabled,
+    Automatic,
+    Interactive,
+}
+
+impl fmt::Display for NullAnalysisMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NullAnalysisMode::Disabled => "disabled",
+            NullAnalysisMode::Automatic => "automatic",
+            NullAnalysisMode::Interactive => "interactive",
+        })
+    }
+}
+
+/// `java.format.settings.*`, for teams that share an Eclipse formatter
+/// profile (`eclipse-formatter.xml`) instead of relying on jdtls' built-in
+/// default style.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct FormatSettings {
+    /// Path to the formatter settings XML, relative to the worktree root
+    /// unless absolute or `~`-prefixed.
+    pub settings_url: Option<String>,
+    /// Which `<profile>` in `settings_url` to use, when the file defines
+    /// more than one. jdtls uses the first profile in the file if unset.
+    pub settings_profile: Option<String>,
+}
+
+/// `java.compile.nullAnalysis.*` settings, for projects that want jdtls to
+/// flag missing/incorrect `@Nullable`/`@NonNull` usage. `nonnull`/`nullable`
+/// let a project point jdtls at its own annotation types (e.g. JSR305's
+/// `javax.annotation.Nonnull`) instead of jdtls' Eclipse-annotation
+/// defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct NullAnalysisSettings {
+    pub mode: NullAnalysisMode,
+    pub nonnull: Vec<String>,
+    pub nullable: Vec<String>,
+}
+
+/// Extension settings, read from the `lsp.jdtls.settings` block of the
+/// user/project `settings.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct JavaSettings {
+    pub debug: DebugSettings,
+    /// `java.configuration.runtimes`, so a project can compile against a JDK
+    /// other than whatever jdtls itself is running on (e.g. a legacy module
+    /// pinned to Java 8 while jdtls runs on 21+). Also consulted by
+    /// [`runtime::get_java_home`] to resolve a `java_home`/`jdk_auto_download`
+    /// alias like `"JavaSE-17"` to a literal path.
+    pub runtimes: Vec<RuntimeConfig>,
+    pub check_updates: CheckUpdates,
+    pub max_concurrent_builds: Option<u32>,
+    pub gradle_offline: Option<bool>,
+    pub lombok: LombokSettings,
+    pub workspace_name: Option<String>,
+    pub quiet: bool,
+    /// Gates `crate::log::debug` output (e.g. per-download progress lines),
+    /// off by default so routine use stays as quiet as `info`/`warn` already
+    /// are. Independent of `quiet`, which still wins: a quiet project never
+    /// sees debug lines even with this set.
+    pub verbose_logging: bool,
+    pub prefer_project_jdtls: bool,
+    pub gradle_wrapper_enabled: Option<bool>,
+    pub generated_source_excludes: Vec<String>,
+    pub data_dir_hash_length: Option<usize>,
+    /// Overrides jdtls' `-data` directory (see `jdtls::get_jdtls_data_path`)
+    /// with a literal path, used verbatim after `~`/relative expansion,
+    /// instead of the hash this extension would otherwise compute from
+    /// `workspace_name`/the worktree root. For backup policies or index
+    /// debugging that need the data directory somewhere specific (alongside
+    /// the project, on a separate disk); `data_dir_hash_length` has no
+    /// effect once this is set.
+    pub jdtls_data_dir: Option<String>,
+    pub source_paths: Vec<String>,
+    /// Overrides the `User-Agent` header sent with jdtls/JDK download
+    /// requests, for enterprise proxies that filter on it. Defaults to
+    /// `util::DEFAULT_USER_AGENT` when unset.
+    pub http_user_agent: Option<String>,
+    /// Raw `extendedClientCapabilities` entries (e.g.
+    /// `classFileContentsSupport`, `overrideMethodsPromptSupport`), merged
+    /// into `initializationOptions` verbatim so users can turn on jdtls
+    /// features as they're added upstream without waiting on us to add a
+    /// dedicated setting for each one.
+    pub extended_capabilities: Map<String, Value>,
+    /// Loose jars (or glob patterns like `lib/**/*.jar`) to feed jdtls via
+    /// `java.project.referencedLibraries`, for projects that aren't built
+    /// with Maven/Gradle. Glob expansion happens on jdtls' side; we only
+    /// resolve a leading relative path against the worktree root.
+    pub referenced_libraries: Vec<String>,
+    /// The JDK jdtls itself should run on (distinct from a project's
+    /// `runtimes`, which pick the compiler/runtime JDK for the *project*).
+    /// Sets `JAVA_HOME` for the launched jdtls process when present.
+    pub java_home: Option<String>,
+    /// When `java_home` and the `JAVA_HOME` environment variable are both
+    /// unset, fall back to sdkman's `~/.sdkman/candidates/java/current`
+    /// symlink target. Off by default so users who intentionally rely on
+    /// `PATH` (and happen to have sdkman installed for other JDKs) aren't
+    /// surprised by jdtls launching on a different one.
+    pub use_sdkman: bool,
+    /// Overrides for `-Declipse.product`/`-Declipse.application`, for
+    /// custom-built jdtls forks/distributions that ship under different
+    /// Eclipse product ids. Blank values are treated as unset.
+    pub jdtls_product: Option<String>,
+    pub jdtls_application: Option<String>,
+    /// Escape hatch that replaces jdtls' computed launch args (the product/
+    /// application flags) entirely, for setups no individual config key
+    /// covers. `-data <path>` is still appended after these. Unsupported —
+    /// bypasses this extension's own version gating for launch behavior.
+    pub jdtls_launch_args_override: Vec<String>,
+    /// Adds JVM properties that keep jdtls' embedded Eclipse p2
+    /// provisioning stack from attempting network activity on startup, for
+    /// locked-down/offline environments. See `jdtls::OFFLINE_JVM_ARGS` for
+    /// the exact flags. Also takes precedence over `jdk_auto_download`: see
+    /// `jdk::guard_offline_auto_download`.
+    pub offline: bool,
+    /// Whether jdtls may be run on a JDK this extension downloads itself
+    /// (see `jdk::try_to_fetch_and_install_latest_jdk`), rather than one
+    /// resolved from `java_home`/the environment. Ignored when `offline` is
+    /// set, which always wins.
+    pub jdk_auto_download: bool,
+    /// Pins `jdk_auto_download` to a single JDK vendor instead of trying
+    /// Corretto first and falling back to Temurin — see
+    /// `jdk::JdkProvider`. Unset tries both, in that order.
+    pub jdk_provider: Option<crate::jdk::JdkProvider>,
+    /// Major JDK version `jdk_auto_download` fetches (e.g. `21` to pin an
+    /// LTS release instead of whatever is currently latest). Rejected if
+    /// below jdtls' own minimum — see `runtime::JDTLS_MINIMUM_JAVA_VERSION`.
+    /// Unset fetches the latest Corretto/Temurin release.
+    pub jdk_version: Option<u32>,
+    /// How much detail `label_for_completion` keeps in a completion's
+    /// display label. Defaults to `detailed` (jdtls' own labels, unchanged).
+    pub completion_label_style: CompletionLabelStyle,
+    /// `-Xms` for the launched jdtls JVM (e.g. `"512m"`). Left to the JVM's
+    /// own default when unset; an invalid value is warned about and dropped.
+    pub jvm_initial_heap: Option<String>,
+    /// `-Xmx` for the launched jdtls JVM (e.g. `"4G"`). Defaults to `1G`
+    /// when unset or invalid — large monorepos should raise this explicitly
+    /// rather than everyone paying for a bigger default heap.
+    pub jvm_max_heap: Option<String>,
+    /// Extra flags spliced into the jdtls JVM's launch args (e.g.
+    /// `["-XX:+UseParallelGC", "-Dlog.level=ALL"]`), for power users who
+    /// need something no dedicated setting covers. Flags jdtls' own launch
+    /// machinery relies on (`-data`, `-jar`, `-configuration`) are dropped
+    /// with a warning rather than passed through.
+    pub jvm_extra_args: Vec<String>,
+    /// Absolute paths to extra jdtls plugin bundle jars, merged into
+    /// `initializationOptions.bundles` (e.g. jars extracted from a Bazel p2
+    /// repository, for projects that need bundles beyond what this
+    /// extension ships itself).
+    pub bundles: Vec<String>,
+    /// Whether jdtls should fetch source jars for Maven/Gradle dependencies,
+    /// so hover docs show real source instead of decompiled bytecode and
+    /// stepping into a dependency while debugging lands on its actual
+    /// source. Off by default: fetching every dependency's sources adds
+    /// noticeable bandwidth and import time on a large project.
+    pub download_sources: bool,
+    /// `java.compile.nullAnalysis.*` passthrough — see [`NullAnalysisSettings`].
+    pub null_analysis: NullAnalysisSettings,
+    /// `java.format.settings.*` passthrough — see [`FormatSettings`].
+    pub format: FormatSettings,
+    /// Base URL of a corporate proxy/mirror (e.g. an internal Nexus raw
+    /// repository) that jdtls, JDK, and lombok downloads should be routed
+    /// through instead of their real upstream hosts (`download.eclipse.org`,
+    /// `corretto.aws`, `api.adoptium.net`, `projectlombok.org`), for
+    /// environments that can't reach those hosts directly. See
+    /// `util::apply_download_mirror` for the exact URL rewrite.
+    pub download_mirror: Option<String>,
+    /// One-shot escape hatch for a half-broken jdtls install (e.g. a
+    /// manually deleted plugin jar): deletes the installed build directory
+    /// and redownloads it on the next language server start, bypassing
+    /// `check_updates`. Leaving this set after the repair just means every
+    /// restart reinstalls — it isn't cleared automatically — so it should
+    /// be turned back off once jdtls is healthy again.
+    pub force_reinstall: bool,
+}
+
+impl JavaSettings {
+    pub fn for_worktree(worktree: &zed::Worktree) -> Result<Self> {
+        let mut settings = zed::settings::LspSettings::for_worktree("jdtls", worktree)
+            .map(|lsp_settings| lsp_settings.settings.unwrap_or_default())
+            .unwrap_or_default();
+
+        let quiet = settings.pointer("/quiet").and_then(Value::as_bool).unwrap_or(false);
+        for warning in migrate_legacy_keys(&mut settings) {
+            crate::log::warn(worktree, quiet, &warning);
+        }
+        warn_on_misshapen_fields(worktree, &settings);
+
+        serde_json::from_value(settings).map_err(|err| format!("invalid jdtls settings: {err}"))
+    }
+
+    /// Reads the raw `java.*` namespace wherever a user might have put it:
+    /// the expected `lsp.jdtls.settings.java` location, or the legacy
+    /// `lsp.jdtls.initialization_options.settings.java` shape carried over
+    /// from VS Code's flat `java.home`-style config. Settings found in both
+    /// places are merged, with `settings.java` winning conflicts; logs
+    /// which shape(s) were found so a "java.home is ignored" report is easy
+    /// to diagnose.
+    pub fn raw_java_namespace(worktree: &zed::Worktree, quiet: bool) -> Value {
+        let lsp_settings = zed::settings::LspSettings::for_worktree("jdtls", worktree).ok();
+
+        let from_settings = lsp_settings
+            .as_ref()
+            .and_then(|lsp_settings| lsp_settings.settings.as_ref())
+            .and_then(|value| value.pointer("/java"))
+            .cloned();
+        let from_initialization_options = lsp_settings
+            .as_ref()
+            .and_then(|lsp_settings| lsp_settings.initialization_options.as_ref())
+            .and_then(|value| value.pointer("/settings/java"))
+            .cloned();
+
+        match (&from_settings, &from_initialization_options) {
+            (Some(_), Some(_)) => crate::log::info(
+                worktree,
+                quiet,
+                "found `java.*` settings in both `settings` and the legacy `initialization_options.settings` shape; merging both",
+            ),
+            (Some(_), None) => {}
+            (None, Some(_)) => crate::log::info(
+                worktree,
+                quiet,
+                "reading `java.*` settings from the legacy `initialization_options.settings` shape",
+            ),
+            (None, None) => {}
+        }
+
+        let mut merged = Value::Object(Map::new());
+        if let Some(from_initialization_options) = from_initialization_options {
+            deep_merge(&mut merged, &from_initialization_options);
+        }
+        if let Some(from_settings) = from_settings {
+            deep_merge(&mut merged, &from_settings);
+        }
+        merged
+    }
+
+    /// Merges build tuning settings (`java.maxConcurrentBuilds`,
+    /// `java.import.gradle.offline.enabled`) into the workspace
+    /// configuration sent to jdtls. `worktree_root` resolves relative
+    /// `source_paths` entries; `home_dir` (the worktree's `$HOME`, if known)
+    /// resolves a `~`-prefixed `format.settings_url`.
+    pub fn merge_tuning_into(&self, options: &mut Value, worktree_root: &str, home_dir: Option<&str>) -> Result<()> {
+        if let Some(max_concurrent_builds) = self.max_concurrent_builds {
+            let java = json_object_mut(options, &["java"])?;
+            java.insert("maxConcurrentBuilds".into(), Value::from(max_concurrent_builds));
+        }
+
+        if let Some(gradle_offline) = self.gradle_offline {
+            let gradle_offline_settings = json_object_mut(options, &["java", "import", "gradle", "offline"])?;
+            gradle_offline_settings.insert("enabled".into(), Value::from(gradle_offline));
+        }
+
+        if let Some(gradle_wrapper_enabled) = self.gradle_wrapper_enabled {
+            let gradle_wrapper = json_object_mut(options, &["java", "import", "gradle", "wrapper"])?;
+            gradle_wrapper.insert("enabled".into(), Value::from(gradle_wrapper_enabled));
+        }
+
+        if !self.generated_source_excludes.is_empty() {
+            let java = json_object_mut(options, &["java", "import"])?;
+            java.insert(
+                "exclusions".into(),
+                Value::Array(
+                    self.generated_source_excludes
+                        .iter()
+                        .cloned()
+                        .map(Value::String)
+                        .collect(),
+                ),
+            );
+        }
+
+        if !self.runtimes.is_empty() {
+            let configuration = json_object_mut(options, &["java", "configuration"])?;
+            configuration.insert(
+                "runtimes".into(),
+                Value::Array(
+                    self.runtimes
+                        .iter()
+                        .map(|runtime| {
+                            let mut entry = Map::new();
+                            entry.insert("name".into(), Value::String(runtime.name.clone()));
+                            entry.insert("path".into(), Value::String(resolve_relative(&runtime.path, worktree_root)));
+                            if runtime.default {
+                                entry.insert("default".into(), Value::Bool(true));
+                            }
+                            Value::Object(entry)
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
+        if !self.source_paths.is_empty() {
+            let project = json_object_mut(options, &["java", "project"])?;
+            project.insert(
+                "sourcePaths".into(),
+                Value::Array(self.resolved_source_paths(worktree_root)),
+            );
+        }
+
+        if !self.referenced_libraries.is_empty() {
+            let project = json_object_mut(options, &["java", "project"])?;
+            let mut entries: Vec<Value> = project
+                .get("referencedLibraries")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            for path in &self.referenced_libraries {
+                let resolved = Value::String(resolve_relative(path, worktree_root));
+                if !entries.contains(&resolved) {
+                    entries.push(resolved);
+                }
+            }
+            project.insert("referencedLibraries".into(), Value::Array(entries));
+        }
+
+        let lombok_support = json_object_mut(options, &["java", "jdt", "ls", "lombokSupport"])?;
+        lombok_support.insert("enabled".into(), Value::from(self.lombok.lombok_support));
+
+        if self.download_sources {
+            json_object_mut(options, &["java", "eclipse"])?.insert("downloadSources".into(), Value::Bool(true));
+            json_object_mut(options, &["java", "maven"])?.insert("downloadSources".into(), Value::Bool(true));
+            json_object_mut(options, &["java", "gradle"])?.insert("downloadSources".into(), Value::Bool(true));
+        }
+
+        let null_analysis = json_object_mut(options, &["java", "compile", "nullAnalysis"])?;
+        null_analysis.insert("mode".into(), Value::String(self.null_analysis.mode.to_string()));
+        if !self.null_analysis.nonnull.is_empty() {
+            null_analysis.insert(
+                "nonnull".into(),
+                Value::Array(self.null_analysis.nonnull.iter().cloned().map(Value::String).collect()),
+            );
+        }
+        if !self.null_analysis.nullable.is_empty() {
+            null_analysis.insert(
+                "nullable".into(),
+                Value::Array(self.null_analysis.nullable.iter().cloned().map(Value::String).collect()),
+            );
+        }
+
+        if let Some(settings_url) = self.format.settings_url.as_deref() {
+            let expanded = crate::util::expand_home_path(settings_url, home_dir);
+            let format_settings = json_object_mut(options, &["java", "format", "settings"])?;
+            format_settings.insert("url".into(), Value::String(resolve_relative(&expanded, worktree_root)));
+            if let Some(profile) = self.format.settings_profile.as_deref() {
+                format_settings.insert("profile".into(), Value::String(profile.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds jdtls' `initializationOptions`. `bundles` is seeded from the
+    /// user's `bundles` setting (absolute jar paths, e.g. extracted Bazel p2
+    /// jars); we don't ship any java-debug plugin bundles of our own yet,
+    /// but once we do, they should be appended here rather than replacing
+    /// this array, so they coexist with whatever the user configured.
+    ///
+    /// `classFileContentsSupport` defaults to enabled so jumping into a
+    /// `.class` in a dependency resolves jdtls' decompiled `jdt://` content
+    /// instead of failing outright; `extended_capabilities` can still
+    /// override it back to `false`. Note this only gets jdtls to *offer*
+    /// the content — actually opening a `jdt://` URI as a virtual document
+    /// depends on Zed's editor core resolving that scheme, which is outside
+    /// what this extension's API surface controls.
+    pub fn build_initialization_options(&self) -> Value {
+        let mut extended_capabilities = Map::new();
+        extended_capabilities.insert("classFileContentsSupport".into(), Value::Bool(true));
+        extended_capabilities.extend(self.extended_capabilities.clone());
+
+        serde_json::json!({
+            "bundles": self.bundles.clone(),
+            "extendedClientCapabilities": Value::Object(extended_capabilities),
+        })
+    }
+
+    /// Resolves `source_paths` against `worktree_root` and merges in the
+    /// conventional Maven/Gradle source roots, so pointing jdtls at a
+    /// project's nonstandard source dirs doesn't also blind it to any
+    /// standard ones that happen to coexist.
+    fn resolved_source_paths(&self, worktree_root: &str) -> Vec<Value> {
+        const CONVENTIONAL_ROOTS: &[&str] = &["src/main/java", "src/test/java"];
+
+        let mut paths: Vec<String> = self.source_paths.clone();
+        for &conventional in CONVENTIONAL_ROOTS {
+            if !paths.iter().any(|path| path == conventional) {
+                paths.push(conventional.to_string());
+            }
+        }
+
+        paths
+            .into_iter()
+            .map(|path| resolve_relative(&path, worktree_root))
+            .map(Value::String)
+            .collect()
+    }
+}
+
+struct LegacyAlias {
+    legacy_pointer: &'static str,
+    modern_pointer: &'static str,
+    modern_key: &'static str,
+}
+
+/// Nested key shapes copied from jdtls' native config (or an older version
+/// of this extension) that now have a flat equivalent. Centralized here so
+/// every reader can assume only the modern shape, instead of each one
+/// growing its own pointer-fallback logic.
+const LEGACY_ALIASES: &[LegacyAlias] = &[
+    LegacyAlias {
+        legacy_pointer: "/java/home",
+        modern_pointer: "/java_home",
+        modern_key: "java_home",
+    },
+    LegacyAlias {
+        legacy_pointer: "/java/jdt/ls/lombokSupport/enabled",
+        modern_pointer: "/lombok/lombok_support",
+        modern_key: "lombok.lombok_support",
+    },
+];
+
+/// Moves every legacy-shaped key present in `value` onto its modern
+/// equivalent (only when the modern key isn't already set explicitly), and
+/// returns a one-time deprecation message per key migrated.
+fn migrate_legacy_keys(value: &mut Value) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for alias in LEGACY_ALIASES {
+        let Some(legacy_value) = value.pointer(alias.legacy_pointer).cloned() else {
+            continue;
+        };
+        if value.pointer(alias.modern_pointer).is_some() {
+            continue;
+        }
+
+        let legacy_key = alias.legacy_pointer.trim_start_matches('/').replace('/', ".");
+        warnings.push(format!(
+            "`{legacy_key}` is deprecated, use `{}` instead",
+            alias.modern_key
+        ));
+        let _ = set_pointer(value, alias.modern_pointer, legacy_value);
+    }
+
+    warnings
+}
+
+/// Writes `new_value` at `pointer` (RFC 6901 syntax), creating intermediate
+/// objects as needed.
+fn set_pointer(root: &mut Value, pointer: &str, new_value: Value) -> Result<()> {
+    let segments: Vec<&str> = pointer.trim_start_matches('/').split('/').collect();
+    let (leaf, parents) = segments
+        .split_last()
+        .ok_or_else(|| "cannot set the root pointer".to_string())?;
+    let parent = json_object_mut(root, parents)?;
+    parent.insert((*leaf).to_string(), new_value);
+    Ok(())
+}
+
+/// Fields where "present but the wrong JSON type" almost always means the
+/// user nested their config wrong (e.g. a single string instead of a list)
+/// rather than intentionally opting out — `#[serde(default)]` would
+/// otherwise silently fall back to the default with no indication why.
+const ARRAY_FIELDS: &[&str] = &[
+    "/runtimes",
+    "/generated_source_excludes",
+    "/source_paths",
+    "/referenced_libraries",
+    "/jvm_extra_args",
+    "/bundles",
+    "/null_analysis/nonnull",
+    "/null_analysis/nullable",
+];
+const OBJECT_FIELDS: &[&str] = &["/debug", "/lombok", "/extended_capabilities", "/null_analysis", "/format"];
+
+fn warn_on_misshapen_fields(worktree: &zed::Worktree, raw_settings: &Value) {
+    let quiet = raw_settings
+        .pointer("/quiet")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    for pointer in ARRAY_FIELDS {
+        warn_if_wrong_shape(worktree, quiet, raw_settings, pointer, "an array", Value::is_array);
+    }
+    for pointer in OBJECT_FIELDS {
+        warn_if_wrong_shape(worktree, quiet, raw_settings, pointer, "an object", Value::is_object);
+    }
+}
+
+fn warn_if_wrong_shape(
+    worktree: &zed::Worktree,
+    quiet: bool,
+    raw_settings: &Value,
+    pointer: &str,
+    expected_description: &str,
+    expected: impl Fn(&Value) -> bool,
+) {
+    if let PointerCheck::WrongType { found } = check_pointer_shape(raw_settings, pointer, expected) {
+        crate::log::warn(
+            worktree,
+            quiet,
+            &format!("jdtls setting `{pointer}` should be {expected_description}, found a {found}; ignoring it"),
+        );
+    }
+}
+
+/// Resolves `path` against `root` unless it's already absolute.
+fn resolve_relative(path: &str, root: &str) -> String {
+    if Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        Path::new(root).join(path).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn does_not_add_source_paths_when_unconfigured() {
+        let settings = JavaSettings::default();
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert!(options["java"]["project"].get("sourcePaths").is_none());
+    }
+
+    #[test]
+    fn merges_custom_source_paths_with_conventional_roots() {
+        let settings = JavaSettings {
+            source_paths: vec!["legacy/java".to_string()],
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+
+        let source_paths: Vec<&str> = options["java"]["project"]["sourcePaths"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_str().unwrap())
+            .collect();
+        assert_eq!(
+            source_paths,
+            vec!["/repo/legacy/java", "/repo/src/main/java", "/repo/src/test/java"]
+        );
+    }
+
+    #[test]
+    fn does_not_duplicate_a_conventional_root_already_listed() {
+        let settings = JavaSettings {
+            source_paths: vec!["src/main/java".to_string()],
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+
+        let source_paths = options["java"]["project"]["sourcePaths"].as_array().unwrap();
+        assert_eq!(source_paths.len(), 2);
+    }
+
+    #[test]
+    fn initialization_options_default_to_empty_bundles_and_class_file_support_enabled() {
+        let settings = JavaSettings::default();
+        let options = settings.build_initialization_options();
+        assert_eq!(options["bundles"], serde_json::json!([]));
+        assert_eq!(options["extendedClientCapabilities"]["classFileContentsSupport"], true);
+    }
+
+    #[test]
+    fn initialization_options_pass_through_bundles() {
+        let settings = JavaSettings {
+            bundles: vec!["/opt/bazel-p2/plugin-a.jar".to_string()],
+            ..Default::default()
+        };
+        let options = settings.build_initialization_options();
+        assert_eq!(
+            options["bundles"],
+            serde_json::json!(["/opt/bazel-p2/plugin-a.jar"])
+        );
+    }
+
+    #[test]
+    fn initialization_options_pass_through_extended_capabilities() {
+        let mut extended_capabilities = Map::new();
+        extended_capabilities.insert("overrideMethodsPromptSupport".into(), Value::Bool(true));
+        let settings = JavaSettings {
+            extended_capabilities,
+            ..Default::default()
+        };
+        let options = settings.build_initialization_options();
+        assert_eq!(options["extendedClientCapabilities"]["overrideMethodsPromptSupport"], true);
+    }
+
+    #[test]
+    fn extended_capabilities_can_override_class_file_support_default() {
+        let mut extended_capabilities = Map::new();
+        extended_capabilities.insert("classFileContentsSupport".into(), Value::Bool(false));
+        let settings = JavaSettings {
+            extended_capabilities,
+            ..Default::default()
+        };
+        let options = settings.build_initialization_options();
+        assert_eq!(options["extendedClientCapabilities"]["classFileContentsSupport"], false);
+    }
+
+    #[test]
+    fn migrates_legacy_java_home_to_flat_key() {
+        let mut settings = serde_json::json!({"java": {"home": "/opt/jdk-21"}});
+        let warnings = migrate_legacy_keys(&mut settings);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("java.home"));
+        assert!(warnings[0].contains("java_home"));
+        assert_eq!(settings["java_home"], "/opt/jdk-21");
+    }
+
+    #[test]
+    fn migrates_legacy_lombok_support_key() {
+        let mut settings = serde_json::json!({"java": {"jdt": {"ls": {"lombokSupport": {"enabled": false}}}}});
+        migrate_legacy_keys(&mut settings);
+        assert_eq!(settings["lombok"]["lombok_support"], false);
+    }
+
+    #[test]
+    fn does_not_override_an_explicitly_set_modern_key() {
+        let mut settings = serde_json::json!({
+            "java": {"home": "/opt/legacy-jdk"},
+            "java_home": "/opt/modern-jdk",
+        });
+        let warnings = migrate_legacy_keys(&mut settings);
+        assert!(warnings.is_empty());
+        assert_eq!(settings["java_home"], "/opt/modern-jdk");
+    }
+
+    #[test]
+    fn no_warnings_when_no_legacy_keys_present() {
+        let mut settings = serde_json::json!({"quiet": true});
+        assert!(migrate_legacy_keys(&mut settings).is_empty());
+    }
+
+    #[test]
+    fn resolves_referenced_libraries_relative_to_worktree_root() {
+        let settings = JavaSettings {
+            referenced_libraries: vec!["lib/**/*.jar".to_string()],
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(
+            options["java"]["project"]["referencedLibraries"],
+            serde_json::json!(["/repo/lib/**/*.jar"])
+        );
+    }
+
+    #[test]
+    fn merges_referenced_libraries_with_existing_entries() {
+        let settings = JavaSettings {
+            referenced_libraries: vec!["extra.jar".to_string()],
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({
+            "java": {"project": {"referencedLibraries": ["/repo/vendor/pre-existing.jar"]}}
+        });
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(
+            options["java"]["project"]["referencedLibraries"],
+            serde_json::json!(["/repo/vendor/pre-existing.jar", "/repo/extra.jar"])
+        );
+    }
+
+    #[test]
+    fn does_not_request_sources_when_download_sources_unset() {
+        let settings = JavaSettings::default();
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert!(options.get("java").and_then(|java| java.get("eclipse")).is_none());
+    }
+
+    #[test]
+    fn download_sources_sets_eclipse_maven_and_gradle_flags() {
+        let settings = JavaSettings {
+            download_sources: true,
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(options["java"]["eclipse"]["downloadSources"], true);
+        assert_eq!(options["java"]["maven"]["downloadSources"], true);
+        assert_eq!(options["java"]["gradle"]["downloadSources"], true);
+    }
+
+    #[test]
+    fn null_analysis_defaults_to_disabled_with_no_annotation_lists() {
+        let settings = JavaSettings::default();
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(options["java"]["compile"]["nullAnalysis"]["mode"], "disabled");
+        assert!(options["java"]["compile"]["nullAnalysis"].get("nonnull").is_none());
+    }
+
+    #[test]
+    fn automatic_null_analysis_mode_enables_warnings_without_raw_json() {
+        let settings = JavaSettings {
+            null_analysis: NullAnalysisSettings {
+                mode: NullAnalysisMode::Automatic,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(options["java"]["compile"]["nullAnalysis"]["mode"], "automatic");
+    }
+
+    #[test]
+    fn passes_through_custom_nonnull_and_nullable_annotation_types() {
+        let settings = JavaSettings {
+            null_analysis: NullAnalysisSettings {
+                mode: NullAnalysisMode::Automatic,
+                nonnull: vec!["javax.annotation.Nonnull".to_string()],
+                nullable: vec!["javax.annotation.Nullable".to_string()],
+            },
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(
+            options["java"]["compile"]["nullAnalysis"]["nonnull"],
+            serde_json::json!(["javax.annotation.Nonnull"])
+        );
+        assert_eq!(
+            options["java"]["compile"]["nullAnalysis"]["nullable"],
+            serde_json::json!(["javax.annotation.Nullable"])
+        );
+    }
+
+    #[test]
+    fn leaves_absolute_source_paths_unresolved() {
+        let settings = JavaSettings {
+            source_paths: vec!["/opt/vendor/java".to_string()],
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+
+        let source_paths = options["java"]["project"]["sourcePaths"].as_array().unwrap();
+        assert_eq!(source_paths[0], "/opt/vendor/java");
+    }
+
+    #[test]
+    fn does_not_set_runtimes_when_unconfigured() {
+        let settings = JavaSettings::default();
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert!(options["java"].get("configuration").is_none());
+    }
+
+    #[test]
+    fn merges_runtimes_into_java_configuration() {
+        use crate::runtime::RuntimeConfig;
+
+        let settings = JavaSettings {
+            runtimes: vec![
+                RuntimeConfig {
+                    name: "JavaSE-8".to_string(),
+                    path: "/opt/jdk-8".to_string(),
+                    default: false,
+                },
+                RuntimeConfig {
+                    name: "JavaSE-21".to_string(),
+                    path: "/opt/jdk-21".to_string(),
+                    default: true,
+                },
+            ],
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+
+        assert_eq!(
+            options["java"]["configuration"]["runtimes"],
+            serde_json::json!([
+                {"name": "JavaSE-8", "path": "/opt/jdk-8"},
+                {"name": "JavaSE-21", "path": "/opt/jdk-21", "default": true},
+            ])
+        );
+    }
+
+    #[test]
+    fn resolves_a_relative_runtime_path_against_worktree_root() {
+        use crate::runtime::RuntimeConfig;
+
+        let settings = JavaSettings {
+            runtimes: vec![RuntimeConfig {
+                name: "JavaSE-17".to_string(),
+                path: "vendor/jdk-17".to_string(),
+                default: false,
+            }],
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(options["java"]["configuration"]["runtimes"][0]["path"], "/repo/vendor/jdk-17");
+    }
+
+    #[test]
+    fn does_not_set_format_settings_when_unconfigured() {
+        let settings = JavaSettings::default();
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert!(options["java"].get("format").is_none());
+    }
+
+    #[test]
+    fn resolves_relative_format_settings_url_against_worktree_root() {
+        let settings = JavaSettings {
+            format: FormatSettings {
+                settings_url: Some("eclipse-formatter.xml".to_string()),
+                settings_profile: None,
+            },
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings.merge_tuning_into(&mut options, "/repo", None).unwrap();
+        assert_eq!(
+            options["java"]["format"]["settings"]["url"],
+            "/repo/eclipse-formatter.xml"
+        );
+        assert!(options["java"]["format"]["settings"].get("profile").is_none());
+    }
+
+    #[test]
+    fn expands_tilde_format_settings_url_against_home_dir() {
+        let settings = JavaSettings {
+            format: FormatSettings {
+                settings_url: Some("~/shared/eclipse-formatter.xml".to_string()),
+                settings_profile: Some("TeamStyle".to_string()),
+            },
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        settings
+            .merge_tuning_into(&mut options, "/repo", Some("/home/alice"))
+            .unwrap();
+        assert_eq!(
+            options["java"]["format"]["settings"]["url"],
+            "/home/alice/shared/eclipse-formatter.xml"
+        );
+        assert_eq!(options["java"]["format"]["settings"]["profile"], "TeamStyle");
+    }
+}
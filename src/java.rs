@@ -0,0 +1,297 @@
+//! `zed::Extension` only builds the jdtls command and its config; it isn't
+//! in the LSP message path once jdtls is running. `$/progress` and
+//! `language/status` notifications jdtls sends while importing a project
+//! (e.g. "Importing Gradle project…") flow straight from jdtls to Zed's own
+//! LSP client and are rendered by Zed core, with no hook in the extension
+//! trait this crate implements against for observing or relaying them —
+//! there's no `proxy.mjs`/`LspClient` bridge in this codebase to add a
+//! status-polling method to.
+
+use std::collections::HashMap;
+
+use zed_extension_api::{self as zed, Result};
+
+use crate::completion::{self, CompletionLabelStyle};
+use crate::config::JavaSettings;
+use crate::debugger::Debugger;
+use crate::java_info::JavaVersionCache;
+use crate::jdtls;
+use crate::lombok;
+use crate::lsp::MainClassCache;
+use crate::runtime::{self, RuntimeConfig};
+
+pub struct JavaExtension {
+    /// Only ever constructed and dropped — see [`Debugger`]'s doc comment
+    /// for why nothing reads it back yet.
+    #[allow(dead_code)]
+    debugger: Debugger,
+    main_class_cache: MainClassCache,
+    /// `label_for_completion` isn't given a worktree, only the
+    /// `language_server_id` of whichever jdtls instance sent the
+    /// completion — keyed by that (rather than a single last-writer-wins
+    /// field) so a multi-root workspace with different
+    /// `completion_label_style` settings per root doesn't have one root's
+    /// completions borrow another root's style.
+    completion_label_styles: HashMap<zed::LanguageServerId, CompletionLabelStyle>,
+    /// Avoids re-running `java -version` on every `language_server_command`
+    /// call (e.g. a jdtls restart) when the resolved `java_home` hasn't
+    /// actually changed.
+    java_version_cache: JavaVersionCache,
+}
+
+impl zed::Extension for JavaExtension {
+    fn new() -> Self {
+        Self {
+            debugger: Debugger::new(),
+            main_class_cache: MainClassCache::new(),
+            completion_label_styles: HashMap::new(),
+            java_version_cache: JavaVersionCache::new(),
+        }
+    }
+
+    fn language_server_command(
+        &mut self,
+        language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<zed::Command> {
+        // A fresh jdtls process means any cached main classes may be stale
+        // (e.g. the project changed while the old server was down).
+        self.main_class_cache.invalidate();
+
+        build_language_server_command(language_server_id, worktree, &mut self.java_version_cache)
+            .map_err(|err| format!("{err} ({})", diagnostic_context(worktree)))
+    }
+
+    fn language_server_initialization_options(
+        &mut self,
+        language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<serde_json::Value>> {
+        let settings = JavaSettings::for_worktree(worktree)?;
+        self.completion_label_styles
+            .insert(language_server_id.clone(), settings.completion_label_style);
+        Ok(Some(settings.build_initialization_options()))
+    }
+
+    // `worktree` here is always whichever worktree jdtls is asking settings
+    // for, not necessarily the most specific one for a nested-project setup
+    // (e.g. a root worktree with a Gradle submodule opened inside it). There
+    // is no precedence between them to apply, because `zed_extension_api`
+    // 0.1.0 gives this hook no way to enumerate sibling worktrees to compare
+    // against — only the one it was called with. So the settings resolved
+    // below are always scoped to exactly the worktree jdtls handed us, with
+    // no "prefer the deeper root" logic to apply even if we wanted it.
+    fn language_server_workspace_configuration(
+        &mut self,
+        language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<serde_json::Value>> {
+        let mut settings = JavaSettings::for_worktree(worktree)?;
+        self.completion_label_styles
+            .insert(language_server_id.clone(), settings.completion_label_style);
+        let mut options = serde_json::json!({ "java": JavaSettings::raw_java_namespace(worktree, settings.quiet) });
+
+        Debugger::inject_plugin_into_options(&settings.debug, &mut options)?;
+
+        if settings.jdk_auto_download {
+            if let Some(runtime) = synthesize_auto_downloaded_runtime(&settings.runtimes, &mut self.java_version_cache) {
+                settings.runtimes.push(runtime);
+            }
+        }
+
+        settings.merge_tuning_into(
+            &mut options,
+            &worktree.root_path(),
+            crate::util::shell_env_var(worktree, "HOME").as_deref(),
+        )?;
+
+        if let Some(source_level) = runtime::detect_project_source_level(worktree) {
+            runtime::warn_if_no_runtime_configured(worktree, settings.quiet, &source_level, &settings.runtimes);
+        }
+
+        if let Some(release) = runtime::detect_configured_release(worktree) {
+            runtime::warn_if_release_exceeds_runtime(
+                worktree,
+                settings.quiet,
+                release,
+                runtime::JDTLS_MINIMUM_JAVA_VERSION,
+            );
+        }
+
+        Ok(Some(options))
+    }
+
+    fn label_for_completion(
+        &self,
+        language_server_id: &zed::LanguageServerId,
+        completion: zed::lsp::Completion,
+    ) -> Option<zed::CodeLabel> {
+        // Not given a worktree, but is given which jdtls instance sent this
+        // completion — look up that root's style rather than falling back
+        // to whichever root's settings were resolved most recently.
+        let style = self
+            .completion_label_styles
+            .get(language_server_id)
+            .copied()
+            .unwrap_or_default();
+        Some(completion::build_completion_label(&completion, style))
+    }
+}
+
+/// Resolves the jdtls binary and its launch args for `worktree`. Split out
+/// from the `language_server_command` trait method so callers can attach
+/// [`diagnostic_context`] to whatever error comes out, without needing to
+/// wrap every fallible step individually.
+///
+/// The returned [`zed::Command`] launches jdtls' own binary directly —
+/// there's no `zed::node_binary_path()`/`proxy.mjs` shim in this codebase
+/// for this to preflight-check (see the module doc comment at the top of
+/// this file for the other place that distinction matters). A "Java
+/// extension requires Node" error would be misleading here: this
+/// extension has no Node dependency at all.
+fn build_language_server_command(
+    language_server_id: &zed::LanguageServerId,
+    worktree: &zed::Worktree,
+    java_version_cache: &mut JavaVersionCache,
+) -> Result<zed::Command> {
+    let settings = JavaSettings::for_worktree(worktree)?;
+    let user_agent = crate::util::resolve_user_agent(settings.http_user_agent.as_deref());
+    let install = jdtls::ensure_installed(
+        language_server_id,
+        worktree,
+        settings.quiet,
+        settings.check_updates,
+        settings.prefer_project_jdtls,
+        settings.jdtls_product.as_deref(),
+        settings.jdtls_application.as_deref(),
+        user_agent,
+        settings.download_mirror.as_deref(),
+        settings.force_reinstall,
+    )?;
+
+    let mut args = jdtls::build_jdtls_launch_args(
+        worktree,
+        settings.quiet,
+        settings.workspace_name.as_deref(),
+        settings.data_dir_hash_length,
+        settings.jdtls_data_dir.as_deref(),
+        settings.jdtls_product.as_deref(),
+        settings.jdtls_application.as_deref(),
+        &settings.jdtls_launch_args_override,
+        settings.offline,
+        settings.jvm_initial_heap.as_deref(),
+        settings.jvm_max_heap.as_deref(),
+        &settings.jvm_extra_args,
+    );
+    if let Some(jar_path) = lombok::ensure_installed(language_server_id, &settings.lombok, settings.download_mirror.as_deref())? {
+        if let Some(javaagent) = lombok::javaagent_arg(&jar_path, &settings.lombok) {
+            args.push(jdtls::jvm_arg(&install.binary_path, &javaagent));
+        }
+    }
+
+    let mut env = Vec::new();
+    let java_home = if let Some(java_home) = settings.java_home.as_deref() {
+        Some(runtime::get_java_home(java_home, &settings.runtimes))
+    } else if crate::util::shell_env_var(worktree, "JAVA_HOME").is_some() {
+        None
+    } else if let Some(sdkman_home) = settings.use_sdkman.then(|| runtime::resolve_sdkman_java_home(worktree)).flatten() {
+        Some(sdkman_home)
+    } else if settings.jdk_auto_download {
+        Some(
+            crate::jdk::try_to_fetch_and_install_latest_jdk(
+                language_server_id,
+                worktree,
+                settings.quiet,
+                settings.verbose_logging,
+                settings.offline,
+                settings.jdk_provider,
+                settings.jdk_version,
+                user_agent,
+                settings.download_mirror.as_deref(),
+            )?
+            .java_home,
+        )
+    } else {
+        None
+    };
+    if let Some(java_home) = java_home {
+        let expanded = crate::util::expand_home_path(&java_home, crate::util::shell_env_var(worktree, "HOME").as_deref());
+        let executable = crate::java_info::get_java_executable(&expanded)?;
+        if let Ok(info) = java_version_cache.get_or_detect(&executable) {
+            runtime::warn_if_jdtls_jdk_too_old(worktree, settings.quiet, info.major);
+        }
+        env.push(("JAVA_HOME".to_string(), expanded));
+    }
+    if let Some(tool_options) = runtime::detect_project_java_tool_options(worktree) {
+        env.push(("JAVA_TOOL_OPTIONS".to_string(), tool_options));
+    }
+    runtime::warn_if_java_tool_options_env_set(worktree, settings.quiet);
+
+    Ok(zed::Command {
+        command: install.binary_path,
+        args,
+        env,
+    })
+}
+
+/// Synthesizes a `java.configuration.runtimes` entry for whatever JDK
+/// `jdk_auto_download` already fetched (e.g. `JavaSE-25` for a Corretto 25
+/// install), so projects targeting that release compile correctly without
+/// the user manually declaring a runtime for a JDK this extension installed
+/// on their behalf. Returns `None` if no JDK has been auto-downloaded yet,
+/// its version can't be detected, or `existing_runtimes` already declares a
+/// runtime under that name (an explicit user entry wins).
+fn synthesize_auto_downloaded_runtime(
+    existing_runtimes: &[RuntimeConfig],
+    java_version_cache: &mut JavaVersionCache,
+) -> Option<RuntimeConfig> {
+    let java_home = crate::jdk::installed_java_home()?;
+    let executable = crate::java_info::get_java_executable(&java_home).ok()?;
+    let info = java_version_cache.get_or_detect(&executable).ok()?;
+    let name = format!("JavaSE-{}", info.major);
+
+    if existing_runtimes.iter().any(|runtime| runtime.name == name) {
+        return None;
+    }
+
+    Some(RuntimeConfig {
+        name,
+        path: java_home,
+        default: false,
+    })
+}
+
+/// Compact `platform=... arch=... check_updates=...` summary appended to
+/// user-facing errors, so a pasted error report carries the environment it
+/// happened in without another round trip. Deliberately excludes any
+/// filesystem paths (which can embed a username) — see
+/// [`crate::util::redact_to_basename`] for callers that need to mention a
+/// path in an error and still keep it shareable.
+fn diagnostic_context(worktree: &zed::Worktree) -> String {
+    let settings = JavaSettings::for_worktree(worktree).ok();
+    crate::util::diagnostic_context(&[
+        ("platform", Some(platform_label())),
+        ("arch", Some(architecture_label())),
+        (
+            "check_updates",
+            settings.as_ref().map(|settings| format!("{:?}", settings.check_updates).to_lowercase()),
+        ),
+        (
+            "java_tool_options",
+            Some(
+                (runtime::detect_project_java_tool_options(worktree).is_some()
+                    || crate::util::shell_env_var(worktree, "JAVA_TOOL_OPTIONS").is_some())
+                .to_string(),
+            ),
+        ),
+        ("gradle_project", Some(runtime::is_gradle_project(worktree).to_string())),
+    ])
+}
+
+fn platform_label() -> String {
+    format!("{:?}", zed::current_platform().0).to_lowercase()
+}
+
+fn architecture_label() -> String {
+    format!("{:?}", zed::current_platform().1).to_lowercase()
+}
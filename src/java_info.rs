@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
+
+use zed_extension_api::Result;
+
+/// The structured result of parsing `java -version` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaInfo {
+    pub major: u32,
+    pub vendor: String,
+    pub runtime_name: String,
+    pub full_version: String,
+}
+
+/// Runs `{executable} -version` and parses its output. `java -version`
+/// writes to stderr, but some vendor-patched builds write to stdout, so
+/// both streams are checked.
+pub fn get_java_info(executable: &str) -> Result<JavaInfo> {
+    let output = Command::new(executable)
+        .arg("-version")
+        .output()
+        .map_err(|err| format!("failed to run `{executable} -version`: {err}"))?;
+
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    parse_java_version_output(&combined)
+}
+
+fn parse_java_version_output(output: &str) -> Result<JavaInfo> {
+    let mut lines = output.lines();
+
+    let version_line = lines
+        .next()
+        .ok_or_else(|| "empty `java -version` output".to_string())?;
+    let full_version = version_line
+        .split('"')
+        .nth(1)
+        .ok_or_else(|| format!("could not parse a version string from: {version_line:?}"))?
+        .to_string();
+    let major = parse_major_version(&full_version)
+        .ok_or_else(|| format!("could not parse a major version from: {full_version:?}"))?;
+
+    let runtime_line = lines.next().unwrap_or_default();
+    let runtime_name = runtime_line
+        .split(" Runtime Environment")
+        .next()
+        .unwrap_or(runtime_line)
+        .trim()
+        .to_string();
+    let vendor = runtime_line
+        .split_whitespace()
+        .find_map(|word| word.split('-').next().filter(|_| word.contains('-')))
+        .unwrap_or("Unknown")
+        .to_string();
+
+    Ok(JavaInfo {
+        major,
+        vendor,
+        runtime_name,
+        full_version,
+    })
+}
+
+/// Handles both modern (`21.0.3`) and legacy (`1.8.0_412`) version strings.
+fn parse_major_version(full_version: &str) -> Option<u32> {
+    let mut components = full_version.split(['.', '_']);
+    let first: u32 = components.next()?.parse().ok()?;
+    if first == 1 {
+        components.next()?.parse().ok()
+    } else {
+        Some(first)
+    }
+}
+
+/// Resolves the real `JAVA_HOME` inside an extracted JDK archive.
+///
+/// macOS JDK distributions bundle a full `.jdk`/app layout where the actual
+/// home is nested at `Contents/Home` rather than the archive root, so a
+/// naive `{extracted_dir}/bin/java` lookup fails. Detect that layout and
+/// point at the nested home instead; other platforms are unaffected.
+pub fn resolve_java_home(extracted_dir: &str) -> String {
+    let macos_home = format!("{extracted_dir}/Contents/Home");
+    if Path::new(&macos_home).join("bin/java").exists() {
+        macos_home
+    } else {
+        extracted_dir.to_string()
+    }
+}
+
+/// Builds `<java_home>/bin/java`, erroring out (rather than handing jdtls a
+/// path that doesn't exist) when a `java_home` points at a JRE-less or
+/// otherwise wrong directory — that would otherwise surface as an opaque
+/// jdtls launch failure with no mention of which setting caused it.
+pub fn get_java_executable(java_home: &str) -> Result<String> {
+    let executable = format!("{java_home}/bin/java");
+    if !Path::new(&executable).exists() {
+        return Err(format!(
+            "`java_home` {java_home:?} doesn't contain a `java` executable (looked for {executable:?})"
+        ));
+    }
+    Ok(executable)
+}
+
+/// Caches [`get_java_info`]'s result for a resolved java executable, keyed
+/// by path and the executable's mtime, so repeated `language_server_command`
+/// invocations (e.g. jdtls restarts) don't re-spawn `java -version` on every
+/// call when nothing about the resolved JDK has changed.
+#[derive(Default)]
+pub struct JavaVersionCache {
+    cached: HashMap<String, (SystemTime, JavaInfo)>,
+}
+
+impl JavaVersionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `executable`'s cached `JavaInfo` if its mtime still matches
+    /// what's cached; otherwise re-runs [`get_java_info`] and refreshes the
+    /// cache entry.
+    pub fn get_or_detect(&mut self, executable: &str) -> Result<JavaInfo> {
+        let mtime = std::fs::metadata(executable)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|err| format!("failed to stat {executable}: {err}"))?;
+
+        if let Some((cached_mtime, info)) = self.cached.get(executable) {
+            if *cached_mtime == mtime {
+                return Ok(info.clone());
+            }
+        }
+
+        let info = get_java_info(executable)?;
+        self.cached.insert(executable.to_string(), (mtime, info.clone()));
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_extracted_dir_when_no_macos_layout() {
+        assert_eq!(resolve_java_home("/tmp/does-not-exist"), "/tmp/does-not-exist");
+    }
+
+    #[test]
+    fn parses_temurin_version_output() {
+        let output = "openjdk version \"21.0.3\" 2024-04-16\n\
+                       OpenJDK Runtime Environment Temurin-21.0.3+9 (build 21.0.3+9)\n\
+                       OpenJDK 64-Bit Server VM Temurin-21.0.3+9 (build 21.0.3+9, mixed mode)\n";
+        let info = parse_java_version_output(output).unwrap();
+        assert_eq!(info.major, 21);
+        assert_eq!(info.full_version, "21.0.3");
+        assert_eq!(info.vendor, "Temurin");
+        assert_eq!(info.runtime_name, "OpenJDK");
+    }
+
+    #[test]
+    fn parses_legacy_1_8_version_string() {
+        assert_eq!(parse_major_version("1.8.0_412"), Some(8));
+        assert_eq!(parse_major_version("21.0.3"), Some(21));
+    }
+
+    #[test]
+    fn errors_when_java_binary_is_missing() {
+        let err = get_java_executable("/tmp/does-not-exist").unwrap_err();
+        assert!(err.contains("/tmp/does-not-exist"));
+        assert!(err.contains("bin/java"));
+    }
+}
@@ -0,0 +1,394 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use zed_extension_api::{self as zed, DownloadedFileType, Result};
+
+use crate::java_info;
+use crate::util;
+
+const INSTALL_DIR: &str = ".jdk";
+
+/// Major version auto-download fetches when `jdk_version` is unset: the
+/// latest Corretto/Temurin release, which is always >=
+/// [`runtime::JDTLS_MINIMUM_JAVA_VERSION`](crate::runtime::JDTLS_MINIMUM_JAVA_VERSION).
+const DEFAULT_JDK_VERSION: u32 = 25;
+
+/// Both of these are hardcoded to `x64-linux`/`linux/x64` — neither branches
+/// on OS/architecture at all; every platform gets the same Linux x86-64
+/// archive today. `guard_unsupported_architecture` rejects the one case
+/// that's cheap to detect up front (32-bit x86, which can't run any
+/// 64-bit-only JDK archive at all) before either of these URLs is ever
+/// built; teaching them to vary by OS otherwise is real work this crate
+/// hasn't done yet, not a one-line fix to an existing branch.
+fn corretto_url(version: u32) -> String {
+    format!("https://corretto.aws/downloads/latest/amazon-corretto-{version}-x64-linux-jdk.tar.gz")
+}
+
+fn temurin_url(version: u32) -> String {
+    format!("https://api.adoptium.net/v3/binary/latest/{version}/ga/linux/x64/jdk/hotspot/normal/eclipse")
+}
+
+/// Validates a user-supplied `jdk_version` against jdtls' own minimum,
+/// defaulting to [`DEFAULT_JDK_VERSION`] when unset. jdtls won't run on
+/// anything older, so a version below the minimum is rejected here rather
+/// than left to fail obscurely once jdtls actually launches on it — see
+/// `runtime::JDTLS_MINIMUM_JAVA_VERSION`, the same constant
+/// `build_jdtls_launch_args`'s caller checks an already-resolved JDK
+/// against.
+fn resolve_jdk_version(requested: Option<u32>) -> Result<u32> {
+    let version = requested.unwrap_or(DEFAULT_JDK_VERSION);
+    if version < crate::runtime::JDTLS_MINIMUM_JAVA_VERSION {
+        return Err(format!(
+            "`jdk_version` {version} is below jdtls' minimum of {}; pick a newer JDK version",
+            crate::runtime::JDTLS_MINIMUM_JAVA_VERSION
+        ));
+    }
+    Ok(version)
+}
+
+pub struct JdkInstall {
+    pub java_home: String,
+}
+
+/// `jdk_provider`, for pinning auto-download to a single JDK vendor instead
+/// of this module's default corretto-then-temurin fallback. Useful when a
+/// user already knows one host is unreachable from their network (a
+/// corporate firewall that blocks `corretto.aws` but not
+/// `api.adoptium.net`, or vice versa) and would rather skip straight to the
+/// host that works than pay for — and log a warning about — a doomed first
+/// attempt every time jdtls starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JdkProvider {
+    Corretto,
+    Temurin,
+}
+
+/// Refuses to auto-download a JDK while `offline` mode is enabled, before
+/// any network activity happens. Offline wins over `jdk_auto_download`: a
+/// user who set both almost certainly cares more about "never touch the
+/// network" than about the convenience of not installing a JDK by hand.
+fn guard_offline_auto_download(offline: bool) -> Result<()> {
+    if offline {
+        return Err("auto-download requested but offline mode is enabled; install a JDK locally".to_string());
+    }
+    Ok(())
+}
+
+/// Refuses to auto-download a JDK on a 32-bit x86 host, before any network
+/// activity happens. `corretto_url`/`temurin_url` only ever build a 64-bit
+/// archive, so downloading on `Architecture::X86` would just produce a JDK
+/// that can't run there; failing fast with an actionable message beats a
+/// confusing download or exec error once jdtls tries to launch it.
+fn guard_unsupported_architecture(architecture: zed::Architecture) -> Result<()> {
+    if architecture == zed::Architecture::X86 {
+        return Err("auto-download only supports 64-bit; set `java_home` manually".to_string());
+    }
+    Ok(())
+}
+
+/// Downloads and installs a JDK for jdtls to run on. With `provider` unset,
+/// prefers Corretto and falls back to Temurin/Adoptium if Corretto is
+/// unreachable (mirror outage, rate limiting, etc.); with `provider` set,
+/// only that vendor is tried — see [`JdkProvider`]. Errors immediately,
+/// without any network activity, if `offline` is set — see
+/// [`guard_offline_auto_download`].
+#[allow(clippy::too_many_arguments)]
+pub fn try_to_fetch_and_install_latest_jdk(
+    language_server_id: &zed::LanguageServerId,
+    worktree: &zed::Worktree,
+    quiet: bool,
+    verbose: bool,
+    offline: bool,
+    provider: Option<JdkProvider>,
+    jdk_version: Option<u32>,
+    user_agent: &str,
+    download_mirror: Option<&str>,
+) -> Result<JdkInstall> {
+    guard_offline_auto_download(offline)?;
+    guard_unsupported_architecture(zed::current_platform().1)?;
+    util::create_path_if_not_exists(INSTALL_DIR)?;
+    let version = resolve_jdk_version(jdk_version)?;
+
+    match provider {
+        Some(JdkProvider::Corretto) => {
+            try_to_fetch_and_install_latest_corretto(language_server_id, worktree, quiet, verbose, version, user_agent, download_mirror)
+        }
+        Some(JdkProvider::Temurin) => {
+            try_to_fetch_and_install_latest_temurin(language_server_id, worktree, quiet, verbose, version, user_agent, download_mirror)
+        }
+        None => match try_to_fetch_and_install_latest_corretto(language_server_id, worktree, quiet, verbose, version, user_agent, download_mirror) {
+            Ok(install) => Ok(install),
+            Err(corretto_err) => {
+                crate::log::warn(
+                    worktree,
+                    quiet,
+                    &format!("Corretto download failed ({corretto_err}), falling back to Temurin"),
+                );
+                try_to_fetch_and_install_latest_temurin(language_server_id, worktree, quiet, verbose, version, user_agent, download_mirror)
+            }
+        },
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn try_to_fetch_and_install_latest_corretto(
+    language_server_id: &zed::LanguageServerId,
+    worktree: &zed::Worktree,
+    quiet: bool,
+    verbose: bool,
+    version: u32,
+    user_agent: &str,
+    download_mirror: Option<&str>,
+) -> Result<JdkInstall> {
+    fetch_and_install(
+        language_server_id,
+        worktree,
+        quiet,
+        verbose,
+        "corretto",
+        version,
+        &corretto_url(version),
+        user_agent,
+        download_mirror,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn try_to_fetch_and_install_latest_temurin(
+    language_server_id: &zed::LanguageServerId,
+    worktree: &zed::Worktree,
+    quiet: bool,
+    verbose: bool,
+    version: u32,
+    user_agent: &str,
+    download_mirror: Option<&str>,
+) -> Result<JdkInstall> {
+    fetch_and_install(
+        language_server_id,
+        worktree,
+        quiet,
+        verbose,
+        "temurin",
+        version,
+        &temurin_url(version),
+        user_agent,
+        download_mirror,
+    )
+}
+
+/// `destination` is keyed by `provider-version` (e.g. `.jdk/corretto-21`),
+/// not just `provider`: an unversioned directory would make switching
+/// `jdk_version` a no-op once any version of that provider was already
+/// installed, since the "already installed" check below only looks for a
+/// `bin/java` binary, not which version it is.
+#[allow(clippy::too_many_arguments)]
+fn fetch_and_install(
+    language_server_id: &zed::LanguageServerId,
+    worktree: &zed::Worktree,
+    quiet: bool,
+    verbose: bool,
+    provider: &str,
+    version: u32,
+    download_url: &str,
+    user_agent: &str,
+    download_mirror: Option<&str>,
+) -> Result<JdkInstall> {
+    let download_url = util::apply_download_mirror(download_url, download_mirror);
+    let destination = format!("{INSTALL_DIR}/{provider}-{version}");
+
+    if !Path::new(&destination).join("bin/java").exists()
+        && !Path::new(&destination).join("Contents/Home/bin/java").exists()
+    {
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+        );
+        crate::log::debug(
+            worktree,
+            quiet,
+            verbose,
+            &format!("no {provider} JDK installed yet; downloading from {download_url}"),
+        );
+
+        let expected_sha256 = fetch_sha256_checksum(&download_url, user_agent, download_mirror);
+
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::Downloading,
+        );
+        util::download_archive(
+            &download_url,
+            &destination,
+            DownloadedFileType::GzipTar,
+            user_agent,
+            None,
+            expected_sha256.as_deref(),
+        )?;
+
+        crate::log::debug(
+            worktree,
+            quiet,
+            verbose,
+            &format!("finished downloading and extracting the {provider} JDK to {destination}"),
+        );
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::None,
+        );
+    }
+
+    Ok(JdkInstall {
+        java_home: java_info::resolve_java_home(&destination),
+    })
+}
+
+/// Fetches the `.sha256` sidecar Corretto publishes alongside each JDK
+/// archive and extracts the digest from it. Returns `None` (rather than an
+/// error) when the sidecar can't be fetched or parsed — Temurin doesn't
+/// publish one at this same convention, and a missing checksum shouldn't
+/// block an otherwise-successful install; it just means `download_archive`
+/// skips verification for this provider.
+fn fetch_sha256_checksum(download_url: &str, user_agent: &str, download_mirror: Option<&str>) -> Option<String> {
+    let checksum_url = util::apply_download_mirror(&format!("{download_url}.sha256"), download_mirror);
+    let response = util::fetch_with_retry(&zed::http_client::HttpRequest {
+        method: zed::http_client::HttpMethod::Get,
+        url: checksum_url,
+        headers: vec![("User-Agent".to_string(), user_agent.to_string())],
+        body: None,
+        redirect_policy: zed::http_client::RedirectPolicy::FollowAll,
+    })
+    .ok()?;
+    parse_sha256_checksum_file(&String::from_utf8_lossy(&response.body))
+}
+
+/// Extracts the hex digest from a `.sha256` file's contents. Corretto's
+/// sidecars are a bare hex digest, but some mirrors follow the coreutils
+/// `sha256sum` convention of `<digest>  <filename>`, so only the first
+/// whitespace-delimited token is taken.
+fn parse_sha256_checksum_file(contents: &str) -> Option<String> {
+    let digest = contents.split_whitespace().next()?;
+    (digest.len() == 64 && digest.chars().all(|ch| ch.is_ascii_hexdigit())).then(|| digest.to_lowercase())
+}
+
+/// The already-installed auto-downloaded JDK's resolved `JAVA_HOME`, if one
+/// exists on disk. Checked without attempting a download, so callers that
+/// just want to know whether a JDK is already there (e.g. to synthesize a
+/// `java.configuration.runtimes` entry for it) don't trigger the
+/// download-status machinery [`try_to_fetch_and_install_latest_jdk`] goes
+/// through when nothing is installed yet. Scans `INSTALL_DIR`'s immediate
+/// children rather than a fixed list of paths, since each one is now named
+/// `provider-version` (see [`fetch_and_install`]) and the installed version
+/// isn't known here.
+pub fn installed_java_home() -> Option<String> {
+    let entries = std::fs::read_dir(INSTALL_DIR).ok()?;
+    entries.flatten().find_map(|entry| {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !(name.starts_with("corretto-") || name.starts_with("temurin-")) {
+            return None;
+        }
+        let destination = entry.path();
+        let has_binary =
+            destination.join("bin/java").exists() || destination.join("Contents/Home/bin/java").exists();
+        has_binary.then(|| java_info::resolve_java_home(&destination.to_string_lossy()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_refuses_auto_download_before_any_network_call() {
+        // A `Err` here proves the guard short-circuits before
+        // `try_to_fetch_and_install_latest_corretto`/`_temurin` — neither is
+        // reachable from a plain unit test, so this is what "no network
+        // call happens" looks like at this layer.
+        assert!(guard_offline_auto_download(true).is_err());
+    }
+
+    #[test]
+    fn non_offline_allows_auto_download() {
+        assert!(guard_offline_auto_download(false).is_ok());
+    }
+
+    #[test]
+    fn x86_refuses_auto_download_before_any_network_call() {
+        let err = guard_unsupported_architecture(zed::Architecture::X86).unwrap_err();
+        assert!(err.contains("java_home"));
+    }
+
+    #[test]
+    fn x64_allows_auto_download() {
+        assert!(guard_unsupported_architecture(zed::Architecture::X8664).is_ok());
+    }
+
+    #[test]
+    fn defaults_to_the_latest_jdk_version_when_unset() {
+        assert_eq!(resolve_jdk_version(None).unwrap(), DEFAULT_JDK_VERSION);
+    }
+
+    #[test]
+    fn accepts_a_requested_version_at_or_above_the_minimum() {
+        assert_eq!(
+            resolve_jdk_version(Some(crate::runtime::JDTLS_MINIMUM_JAVA_VERSION)).unwrap(),
+            crate::runtime::JDTLS_MINIMUM_JAVA_VERSION
+        );
+    }
+
+    #[test]
+    fn rejects_a_requested_version_below_the_minimum() {
+        let err = resolve_jdk_version(Some(crate::runtime::JDTLS_MINIMUM_JAVA_VERSION - 1)).unwrap_err();
+        assert!(err.contains("jdk_version"));
+    }
+
+    #[test]
+    fn builds_corretto_url_for_the_requested_version() {
+        assert_eq!(
+            corretto_url(21),
+            "https://corretto.aws/downloads/latest/amazon-corretto-21-x64-linux-jdk.tar.gz"
+        );
+    }
+
+    #[test]
+    fn builds_temurin_url_for_the_requested_version() {
+        assert_eq!(
+            temurin_url(21),
+            "https://api.adoptium.net/v3/binary/latest/21/ga/linux/x64/jdk/hotspot/normal/eclipse"
+        );
+    }
+
+    #[test]
+    fn parses_jdk_provider() {
+        assert_eq!(
+            serde_json::from_value::<JdkProvider>(serde_json::json!("corretto")).unwrap(),
+            JdkProvider::Corretto
+        );
+        assert_eq!(
+            serde_json::from_value::<JdkProvider>(serde_json::json!("temurin")).unwrap(),
+            JdkProvider::Temurin
+        );
+        assert!(serde_json::from_value::<JdkProvider>(serde_json::json!("zulu")).is_err());
+    }
+
+    #[test]
+    fn parses_a_bare_sha256_sidecar() {
+        let digest = "a".repeat(64);
+        assert_eq!(parse_sha256_checksum_file(&digest), Some(digest));
+    }
+
+    #[test]
+    fn parses_a_sha256sum_style_sidecar() {
+        let digest = "b".repeat(64);
+        assert_eq!(
+            parse_sha256_checksum_file(&format!("{digest}  amazon-corretto-25-x64-linux-jdk.tar.gz\n")),
+            Some(digest)
+        );
+    }
+
+    #[test]
+    fn rejects_a_sidecar_that_is_not_a_sha256_digest() {
+        assert_eq!(parse_sha256_checksum_file("not a checksum"), None);
+    }
+}
@@ -0,0 +1,968 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use serde::Deserialize;
+use zed_extension_api::{self as zed, DownloadedFileType, Result};
+
+use crate::util;
+
+const LATEST_TXT_URL: &str = "https://download.eclipse.org/jdtls/milestones/latest.txt";
+const MILESTONES_BASE_URL: &str = "https://download.eclipse.org/jdtls/milestones/latest";
+
+const INSTALL_DIR: &str = ".jdtls";
+const INSTALLED_MARKER: &str = ".jdtls/.installed";
+const CHECKED_ONCE_MARKER: &str = ".jdtls/.checked-once";
+
+/// Deliberately relative, like `INSTALL_DIR`: extensions get their own
+/// working directory from the host, so there's no `$HOME/.cache` (or
+/// `XDG_CACHE_HOME`) resolution anywhere in this file, and no
+/// platform-specific branch to pick between — every install/cache directory
+/// this extension writes (`.jdtls`, `.jdk`, `.lombok`, and this one) lives
+/// under that same sandboxed relative root on every platform.
+const DATA_DIR: &str = ".jdtls/data";
+
+/// Full length of the hex-encoded `DefaultHasher` output used to name a
+/// workspace's `-data` directory. Only referenced from tests below (as the
+/// "no truncation" case for `hash_length`); production call sites pass
+/// `None` for that instead, which means the same thing.
+#[cfg(test)]
+const FULL_HASH_LENGTH: usize = 16;
+
+/// jdtls keeps a per-project index in its `-data` directory; reusing the
+/// same directory across unrelated projects corrupts the index, so each
+/// workspace gets its own. `workspace_name` lets multi-root setups pin an
+/// explicit, stable name instead of relying on the worktree path hash
+/// (handy when the same logical workspace is opened from different paths).
+///
+/// `hash_length` truncates the hex digest to fewer characters for a
+/// shorter, more readable `-data` path; `None` (or a value at or above
+/// `FULL_HASH_LENGTH`) keeps the full hash. We only have `DefaultHasher`
+/// (std, no extra dependency) to work with, so the algorithm itself isn't
+/// user-selectable — only the amount of it we keep.
+///
+/// `data_dir` (the `jdtls_data_dir` setting) overrides all of the above
+/// when set: it's used verbatim, after `~`/relative expansion, instead of
+/// the computed hash. This is for backup policies and index debugging that
+/// care where on disk the `-data` directory lands, not for sharing one
+/// across workspaces — a relative `data_dir` still resolves per-worktree,
+/// same as `workspace_name` without it would mean per-workspace-name.
+pub fn get_jdtls_data_path(
+    worktree: &zed::Worktree,
+    workspace_name: Option<&str>,
+    hash_length: Option<usize>,
+    data_dir: Option<&str>,
+) -> String {
+    if let Some(data_dir) = data_dir {
+        let home = crate::util::shell_env_var(worktree, "HOME");
+        return resolve_data_dir_override(data_dir, home.as_deref(), &worktree.root_path());
+    }
+
+    let key = workspace_name
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| worktree.root_path());
+
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let full_hash = format!("{:x}", hasher.finish());
+    let truncated = match hash_length {
+        Some(len) if len < full_hash.len() => &full_hash[..len],
+        _ => full_hash.as_str(),
+    };
+
+    format!("{DATA_DIR}/{truncated}")
+}
+
+/// Expands `~`/resolves a user-supplied `jdtls_data_dir` against
+/// `worktree_root`, split out from [`get_jdtls_data_path`] so it's testable
+/// without a live `zed::Worktree`.
+fn resolve_data_dir_override(data_dir: &str, home: Option<&str>, worktree_root: &str) -> String {
+    let expanded = crate::util::expand_home_path(data_dir, home);
+    if Path::new(&expanded).is_absolute() {
+        expanded
+    } else {
+        Path::new(worktree_root).join(&expanded).to_string_lossy().into_owned()
+    }
+}
+
+/// Controls how often the extension re-scrapes `latest.txt` for a newer
+/// jdtls milestone. Independent of the `java.zed.checkForUpdates` proxy
+/// command, which always forces a check regardless of this setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckUpdates {
+    /// Check once per install of the extension, then reuse whatever was
+    /// found until the marker is cleared (e.g. by an extension upgrade).
+    #[default]
+    Once,
+    /// Never check; only ever use whatever is already installed.
+    Never,
+}
+
+pub struct JdtlsInstall {
+    pub binary_path: String,
+}
+
+/// Looks for a jdtls launcher already checked into the project (e.g.
+/// vendored via a Gradle/Maven plugin, or a custom build), at the
+/// conventional `./.jdtls/bin/jdtls[.bat]` path.
+pub fn get_jdtls_launcher_from_path(worktree: &zed::Worktree) -> Option<String> {
+    for candidate in ["./.jdtls/bin/jdtls", "./.jdtls/bin/jdtls.bat"] {
+        if worktree.read_text_file(candidate).is_ok() {
+            return Some(candidate.to_string());
+        }
+    }
+    None
+}
+
+/// Whether `binary_path` is the Windows `.bat` launcher rather than the
+/// Unix shell script. The `.bat` launcher takes a different command-line
+/// convention for JVM flags (see [`jvm_arg`]).
+pub fn is_windows_batch_launcher(binary_path: &str) -> bool {
+    binary_path.ends_with(".bat")
+}
+
+/// Formats a raw JVM flag (e.g. `-javaagent:lombok.jar`) for `binary_path`'s
+/// launch args. The `.bat` launcher wraps the rest of its arguments and
+/// only forwards ones prefixed `--jvm-arg=` straight to the JVM, unlike the
+/// Unix `jdtls` script, which takes `-X`/`-D`-style flags directly.
+pub fn jvm_arg(binary_path: &str, flag: &str) -> String {
+    if is_windows_batch_launcher(binary_path) {
+        format!("--jvm-arg={flag}")
+    } else {
+        flag.to_string()
+    }
+}
+
+/// Ensures a jdtls milestone build is installed under
+/// `.jdtls/<build_directory>` and returns the path to its launcher script,
+/// respecting `check_updates` to avoid hitting `latest.txt` on every
+/// language server start. When `prefer_project_jdtls` is set and the
+/// project vendors its own launcher, that takes priority over anything we'd
+/// download.
+///
+/// If an update check is due but fails (mirror outage, rate limiting) while
+/// a build from a previous check is already installed, that existing build
+/// is used instead of failing the whole language server startup — a
+/// transient network hiccup shouldn't take down a project that already has
+/// a working jdtls.
+///
+/// `force_reinstall` deletes whatever build is currently installed before
+/// any of the above, so a half-broken install (e.g. a manually deleted
+/// plugin jar) self-repairs on the next start instead of limping along.
+#[allow(clippy::too_many_arguments)]
+pub fn ensure_installed(
+    language_server_id: &zed::LanguageServerId,
+    worktree: &zed::Worktree,
+    quiet: bool,
+    check_updates: CheckUpdates,
+    prefer_project_jdtls: bool,
+    jdtls_product: Option<&str>,
+    jdtls_application: Option<&str>,
+    user_agent: &str,
+    download_mirror: Option<&str>,
+    force_reinstall: bool,
+) -> Result<JdtlsInstall> {
+    if prefer_project_jdtls {
+        if let Some(binary_path) = get_jdtls_launcher_from_path(worktree) {
+            return Ok(JdtlsInstall { binary_path });
+        }
+    }
+
+    util::create_path_if_not_exists(INSTALL_DIR)?;
+
+    if force_reinstall {
+        delete_installed_jdtls_build()?;
+    }
+
+    let fingerprint = install_fingerprint(prefer_project_jdtls, jdtls_product, jdtls_application);
+    let existing_build_directory = installed_build_directory();
+
+    if let Some(build_directory) = &existing_build_directory {
+        let skip_check = match check_updates {
+            CheckUpdates::Never => true,
+            CheckUpdates::Once => has_checked_once(&fingerprint),
+        };
+        if skip_check {
+            return Ok(JdtlsInstall {
+                binary_path: binary_path_for(build_directory),
+            });
+        }
+    }
+
+    match force_check_for_updates(language_server_id, user_agent, download_mirror) {
+        Ok(install) => {
+            mark_checked_once(&fingerprint)?;
+            Ok(install)
+        }
+        Err(err) => match existing_build_directory {
+            Some(build_directory) => {
+                crate::log::warn(
+                    worktree,
+                    quiet,
+                    &format!("failed to check for jdtls updates ({err}); continuing with the already-installed build"),
+                );
+                Ok(JdtlsInstall {
+                    binary_path: binary_path_for(&build_directory),
+                })
+            }
+            None => Err(err),
+        },
+    }
+}
+
+/// Fingerprints the settings that influence what `ensure_installed` treats
+/// as "the target build", so a settings change (e.g. pinning a different
+/// `jdtls_product`/`jdtls_application`, or any future pinned-version
+/// setting added alongside them) invalidates a stale `Once` marker instead
+/// of silently reusing whatever was checked before the change.
+fn install_fingerprint(prefer_project_jdtls: bool, jdtls_product: Option<&str>, jdtls_application: Option<&str>) -> String {
+    format!(
+        "{prefer_project_jdtls}|{}|{}",
+        jdtls_product.unwrap_or_default(),
+        jdtls_application.unwrap_or_default(),
+    )
+}
+
+/// Fetches `latest.txt`, installs the milestone build if it isn't already
+/// present, and returns its launcher path. Ignores `check_updates` — used
+/// both by `ensure_installed` on a cache miss and by the
+/// `java.zed.checkForUpdates` proxy command to force a manual refresh.
+///
+/// Nothing in this file (or `jdk.rs`/`lombok.rs`) ever calls the GitHub API
+/// — version discovery goes through Eclipse's `latest.txt`, not GitHub tags
+/// or releases — so there's no `Authorization: Bearer` header to add for a
+/// `github_token` setting here. `download_mirror` (see
+/// `util::apply_download_mirror`) is this extension's actual escape hatch
+/// for networks that can't reach the real upstream hosts directly.
+pub fn force_check_for_updates(
+    language_server_id: &zed::LanguageServerId,
+    user_agent: &str,
+    download_mirror: Option<&str>,
+) -> Result<JdtlsInstall> {
+    let latest_version_build = fetch_latest_version_build(user_agent, download_mirror)?;
+    let build_directory = build_directory_for(&latest_version_build);
+
+    let installed_directory = if Path::new(&binary_path_for(&build_directory)).exists() {
+        build_directory
+    } else {
+        try_to_fetch_and_install_latest_jdtls(
+            language_server_id,
+            &latest_version_build,
+            &build_directory,
+            user_agent,
+            download_mirror,
+        )?
+    };
+
+    fs::write(INSTALLED_MARKER, &installed_directory).map_err(|err| err.to_string())?;
+
+    Ok(JdtlsInstall {
+        binary_path: binary_path_for(&installed_directory),
+    })
+}
+
+fn binary_path_for(build_directory: &str) -> String {
+    format!("{INSTALL_DIR}/{build_directory}/bin/jdtls")
+}
+
+fn installed_build_directory() -> Option<String> {
+    fs::read_to_string(INSTALLED_MARKER).ok()
+}
+
+/// Removes whatever jdtls build is currently installed, plus both install
+/// markers, so a subsequent `ensure_installed` call sees a clean slate and
+/// redownloads from scratch. Only the build's top-level directory is
+/// removed (not all of `INSTALL_DIR`), so a workspace's `-data` index under
+/// `DATA_DIR` survives the reinstall.
+fn delete_installed_jdtls_build() -> Result<()> {
+    if let Some(build_directory) = installed_build_directory() {
+        let top_level_directory = top_level_component(&build_directory);
+        let path = format!("{INSTALL_DIR}/{top_level_directory}");
+        if Path::new(&path).exists() {
+            fs::remove_dir_all(&path).map_err(|err| format!("failed to remove {path}: {err}"))?;
+        }
+    }
+    remove_file_if_exists(INSTALLED_MARKER)?;
+    remove_file_if_exists(CHECKED_ONCE_MARKER)?;
+    Ok(())
+}
+
+/// `build_directory` is either the archive's own top-level directory, or
+/// (per [`discover_installed_directory`]) that directory plus one nested
+/// level — either way, the directory actually worth deleting is the first
+/// path component.
+fn top_level_component(build_directory: &str) -> &str {
+    build_directory.split('/').next().unwrap_or(build_directory)
+}
+
+fn remove_file_if_exists(path: &str) -> Result<()> {
+    if Path::new(path).exists() {
+        fs::remove_file(path).map_err(|err| format!("failed to remove {path}: {err}"))?;
+    }
+    Ok(())
+}
+
+/// Returns true only if the marker exists AND was written for the same
+/// `fingerprint` — a fingerprint mismatch means the settings that determine
+/// the install target changed since the last check, so it doesn't count.
+fn has_checked_once(fingerprint: &str) -> bool {
+    fs::read_to_string(CHECKED_ONCE_MARKER)
+        .map(|recorded| recorded == fingerprint)
+        .unwrap_or(false)
+}
+
+fn mark_checked_once(fingerprint: &str) -> Result<()> {
+    fs::write(CHECKED_ONCE_MARKER, fingerprint).map_err(|err| err.to_string())
+}
+
+/// `latest.txt` is a plain-text pointer file Eclipse publishes alongside
+/// each milestone (not a GitHub tags API or any other structured index), so
+/// there's no JSON to parse here — but a mirror outage or a captive portal
+/// can still hand back an HTML error page on a 200 response, which would
+/// otherwise sail through as a "version" and fail confusingly much later
+/// during extraction. `looks_like_a_jdtls_tarball_filename` catches that
+/// case up front instead.
+fn fetch_latest_version_build(user_agent: &str, download_mirror: Option<&str>) -> Result<String> {
+    let url = util::apply_download_mirror(LATEST_TXT_URL, download_mirror);
+    let response = util::fetch_with_retry(&zed::http_client::HttpRequest {
+        method: zed::http_client::HttpMethod::Get,
+        url: url.clone(),
+        headers: vec![("User-Agent".to_string(), user_agent.to_string())],
+        body: None,
+        redirect_policy: zed::http_client::RedirectPolicy::FollowAll,
+    })?;
+
+    let build = String::from_utf8_lossy(&response.body).trim().to_string();
+    if !looks_like_a_jdtls_tarball_filename(&build) {
+        return Err(format!("unexpected response from {url}: {build:?}"));
+    }
+    Ok(build)
+}
+
+/// A real `latest.txt` response is a bare `jdt-language-server-*.tar.gz`
+/// filename with no whitespace; anything else (an HTML error page, an empty
+/// body, a redirect notice) fails this check.
+fn looks_like_a_jdtls_tarball_filename(value: &str) -> bool {
+    value.starts_with("jdt-language-server-") && value.ends_with(".tar.gz") && !value.contains(char::is_whitespace)
+}
+
+/// Fetches the `.sha1` sidecar Eclipse publishes alongside each milestone
+/// tarball and extracts the digest from it. Returns `None` (rather than an
+/// error) when the sidecar can't be fetched or parsed — not every milestone
+/// is guaranteed to publish one, and a missing checksum shouldn't block an
+/// otherwise-successful install; it just means `download_archive` skips
+/// verification for this build.
+fn fetch_sha1_checksum(download_url: &str, user_agent: &str, download_mirror: Option<&str>) -> Option<String> {
+    let checksum_url = util::apply_download_mirror(&format!("{download_url}.sha1"), download_mirror);
+    let response = util::fetch_with_retry(&zed::http_client::HttpRequest {
+        method: zed::http_client::HttpMethod::Get,
+        url: checksum_url,
+        headers: vec![("User-Agent".to_string(), user_agent.to_string())],
+        body: None,
+        redirect_policy: zed::http_client::RedirectPolicy::FollowAll,
+    })
+    .ok()?;
+
+    parse_sha1_checksum_file(&String::from_utf8_lossy(&response.body))
+}
+
+/// Extracts the hex digest from a `.sha1` file's contents. Eclipse's
+/// sidecars are normally a bare hex digest, but some mirrors follow the
+/// coreutils `sha1sum` convention of `<digest>  <filename>`, so only the
+/// first whitespace-delimited token is taken.
+fn parse_sha1_checksum_file(contents: &str) -> Option<String> {
+    let digest = contents.split_whitespace().next()?;
+    (digest.len() == 40 && digest.chars().all(|ch| ch.is_ascii_hexdigit())).then(|| digest.to_lowercase())
+}
+
+/// Derives the directory the milestone tarball extracts into from the
+/// `latest.txt` filename (e.g. `jdt-language-server-1.46.1-202504011455.tar.gz`).
+/// This must match the top-level directory name `DownloadedFileType::GzipTar`
+/// extracts the archive into, which is the filename with `.tar.gz` removed.
+fn build_directory_for(latest_version_build: &str) -> String {
+    let trimmed = latest_version_build.trim();
+    trimmed
+        .strip_suffix(".tar.gz")
+        .unwrap_or(trimmed)
+        .to_string()
+}
+
+/// Downloads and extracts the milestone build, then returns the directory
+/// (relative to `INSTALL_DIR`) that actually ended up holding `bin/jdtls` —
+/// see [`discover_installed_directory`] for why that isn't always
+/// `build_directory` itself.
+fn try_to_fetch_and_install_latest_jdtls(
+    language_server_id: &zed::LanguageServerId,
+    latest_version_build: &str,
+    build_directory: &str,
+    user_agent: &str,
+    download_mirror: Option<&str>,
+) -> Result<String> {
+    zed::set_language_server_installation_status(
+        language_server_id,
+        &zed::LanguageServerInstallationStatus::Downloading,
+    );
+
+    let download_url = format!("{MILESTONES_BASE_URL}/{latest_version_build}");
+    let destination = format!("{INSTALL_DIR}/{build_directory}");
+    let expected_sha1 = fetch_sha1_checksum(&download_url, user_agent, download_mirror);
+    let mirrored_download_url = util::apply_download_mirror(&download_url, download_mirror);
+
+    util::download_archive(
+        &mirrored_download_url,
+        &destination,
+        DownloadedFileType::GzipTar,
+        user_agent,
+        expected_sha1.as_deref(),
+        None,
+    )?;
+
+    util::remove_all_files_except(INSTALL_DIR, build_directory)?;
+
+    let installed_directory = discover_installed_directory(build_directory)?;
+    verify_install(&binary_path_for(&installed_directory))?;
+
+    Ok(installed_directory)
+}
+
+/// `build_directory` is derived from the archive's own filename
+/// (`build_directory_for`), so `bin/jdtls` is normally right under it. But
+/// if Eclipse ever renames the tarball's top-level directory independently
+/// of the filename it's published under, the binary instead lands nested
+/// one level deeper, under whatever that top-level directory is actually
+/// called. Rather than fail outright (which previously left
+/// `INSTALLED_MARKER` unwritten and caused a fresh download on every
+/// subsequent launch), fall back to scanning `build_directory`'s immediate
+/// children for one that has it.
+fn discover_installed_directory(build_directory: &str) -> Result<String> {
+    if Path::new(&binary_path_for(build_directory)).exists() {
+        return Ok(build_directory.to_string());
+    }
+
+    let root = format!("{INSTALL_DIR}/{build_directory}");
+    let entries = fs::read_dir(&root).map_err(|err| format!("failed to read {root}: {err}"))?;
+    let names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    let children: Vec<(&str, bool)> = names
+        .iter()
+        .map(|name| (name.as_str(), Path::new(&root).join(name).join("bin/jdtls").exists()))
+        .collect();
+
+    pick_nested_directory(&children)
+        .map(|nested| format!("{build_directory}/{nested}"))
+        .ok_or_else(|| {
+            format!(
+                "jdtls archive extracted to `{build_directory}` but no `bin/jdtls` was found there or in any \
+                 immediate subdirectory; Eclipse may have changed the archive layout"
+            )
+        })
+}
+
+/// Pure half of [`discover_installed_directory`]: given `build_directory`'s
+/// immediate children and whether each one contains `bin/jdtls`, picks the
+/// first match. Kept separate from the real directory scan so a
+/// mismatched-layout archive can be exercised without touching the
+/// filesystem.
+fn pick_nested_directory<'a>(children: &[(&'a str, bool)]) -> Option<&'a str> {
+    children.iter().find(|(_, has_binary)| *has_binary).map(|(name, _)| *name)
+}
+
+/// Runs `jdtls --help` as a smoke test right after install, so a corrupted
+/// or non-executable download fails loudly here instead of surfacing as a
+/// confusing "language server exited" error later.
+fn verify_install(binary_path: &str) -> Result<()> {
+    std::process::Command::new(binary_path)
+        .arg("--help")
+        .output()
+        .map_err(|err| format!("jdtls smoke test failed to run {binary_path} --help: {err}"))?;
+    Ok(())
+}
+
+/// Deletes the `-data` directory `get_jdtls_data_path` computes for
+/// `worktree` (with the same `workspace_name`/`hash_length` a caller would
+/// pass to `build_jdtls_launch_args`), so a corrupted jdtls project index
+/// can be reset without the user having to find and delete the hashed
+/// folder by hand. A no-op (not an error) if the directory doesn't exist.
+pub fn clear_jdtls_data(
+    worktree: &zed::Worktree,
+    workspace_name: Option<&str>,
+    hash_length: Option<usize>,
+    data_dir: Option<&str>,
+) -> Result<()> {
+    let data_path = get_jdtls_data_path(worktree, workspace_name, hash_length, data_dir);
+    if Path::new(&data_path).exists() {
+        fs::remove_dir_all(&data_path).map_err(|err| format!("failed to remove {data_path}: {err}"))?;
+    }
+    Ok(())
+}
+
+/// Backing implementation for the `java.zed.clearCaches` proxy command:
+/// removes the update-check marker and the worktree's jdtls `-data`
+/// directory (and, as future caches are added, those alongside them)
+/// without touching an already-installed jdtls binary. Short of
+/// `force_reinstall`, this is the surgical way to recover from e.g. a
+/// yanked milestone stuck in `CHECKED_ONCE_MARKER`'s "already checked"
+/// state, or a corrupted project index.
+///
+/// Not called yet — no command surface (slash command or otherwise) exposes
+/// it to users in this extension version.
+#[allow(dead_code)]
+pub fn clear_caches(
+    worktree: &zed::Worktree,
+    quiet: bool,
+    workspace_name: Option<&str>,
+    hash_length: Option<usize>,
+    data_dir: Option<&str>,
+) -> Result<()> {
+    let mut cleared = Vec::new();
+
+    if Path::new(CHECKED_ONCE_MARKER).exists() {
+        fs::remove_file(CHECKED_ONCE_MARKER).map_err(|err| err.to_string())?;
+        cleared.push(CHECKED_ONCE_MARKER.to_string());
+    }
+
+    let data_path = get_jdtls_data_path(worktree, workspace_name, hash_length, data_dir);
+    if Path::new(&data_path).exists() {
+        clear_jdtls_data(worktree, workspace_name, hash_length, data_dir)?;
+        cleared.push(data_path);
+    }
+
+    if cleared.is_empty() {
+        crate::log::info(worktree, quiet, "no caches to clear");
+    } else {
+        crate::log::info(worktree, quiet, &format!("cleared caches: {}", cleared.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// Checks whether a newer jdtls milestone is available without installing
+/// it, so callers (e.g. a "check for updates" command) can report on
+/// pending updates without paying the download cost.
+///
+/// Not called yet — no command surface exposes an explicit "check for
+/// updates" action in this extension version.
+#[allow(dead_code)]
+pub fn check_jdtls_update(user_agent: &str, download_mirror: Option<&str>) -> Result<Option<String>> {
+    let latest_version_build = fetch_latest_version_build(user_agent, download_mirror)?;
+    let latest_build_directory = build_directory_for(&latest_version_build);
+
+    match installed_build_directory() {
+        Some(installed) if installed == latest_build_directory => Ok(None),
+        _ => Ok(Some(latest_build_directory)),
+    }
+}
+
+/// Eclipse product/application ids jdtls ships under by default. Forks and
+/// custom-built products can override these via `jdtls_product` /
+/// `jdtls_application`.
+const DEFAULT_JDTLS_PRODUCT: &str = "org.eclipse.jdt.ls.core.product";
+const DEFAULT_JDTLS_APPLICATION: &str = "org.eclipse.jdt.ls.core.id1";
+
+/// Falls back to `default` when `value` is absent or blank, so an
+/// accidentally-empty override doesn't launch jdtls with `-Declipse.product=`.
+fn non_empty_or_default<'a>(value: Option<&'a str>, default: &'a str) -> &'a str {
+    match value {
+        Some(value) if !value.trim().is_empty() => value,
+        _ => default,
+    }
+}
+
+/// Computes the leading product/application launch args, before `-data` is
+/// appended. Split out from [`build_jdtls_launch_args`] so this decision —
+/// whether to trust `launch_args_override` verbatim or compute the usual
+/// `-Declipse.*` flags — is testable without a live `zed::Worktree`.
+fn product_and_application_args(
+    jdtls_product: Option<&str>,
+    jdtls_application: Option<&str>,
+    launch_args_override: &[String],
+) -> Vec<String> {
+    if launch_args_override.is_empty() {
+        let product = non_empty_or_default(jdtls_product, DEFAULT_JDTLS_PRODUCT);
+        let application = non_empty_or_default(jdtls_application, DEFAULT_JDTLS_APPLICATION);
+        vec![
+            format!("-Declipse.product={product}"),
+            format!("-Declipse.application={application}"),
+        ]
+    } else {
+        launch_args_override.to_vec()
+    }
+}
+
+/// System properties that keep jdtls' embedded Eclipse p2 provisioning
+/// stack from reaching out on startup, for the `offline` setting. jdtls
+/// doesn't hit p2 update sites during normal operation, but this disables
+/// the unsigned-content prompt p2 would otherwise need a network round trip
+/// to resolve — the one property this extension's maintainers could pin
+/// down without a live jdtls install to test further flags against.
+const OFFLINE_JVM_ARGS: &[&str] = &["-Declipse.p2.unsignedPolicy=allow"];
+
+/// Appends [`OFFLINE_JVM_ARGS`] to `args` when `offline` is set. Split out
+/// so this decision is testable without a live `zed::Worktree`.
+fn append_offline_args(args: &mut Vec<String>, offline: bool) {
+    if offline {
+        args.extend(OFFLINE_JVM_ARGS.iter().map(|arg| arg.to_string()));
+    }
+}
+
+/// `-Xmx` used when `jvm_max_heap` is unset or invalid. jdtls itself
+/// defaults to whatever the JVM picks (usually a quarter of system RAM),
+/// which is generous on small machines and still not enough on large
+/// monorepos, so we pin a middle-ground default rather than leaving it to
+/// the JVM.
+const DEFAULT_MAX_HEAP: &str = "1G";
+
+/// `-Xms`/`-Xmx` accept a bare byte count or one suffixed with `k`/`m`/`g`
+/// (case-insensitive) — the same shape the JVM itself parses, so a value
+/// that fails this can only fail to start jdtls outright.
+fn looks_like_a_heap_size(value: &str) -> bool {
+    let digits_end = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (digits, suffix) = value.split_at(digits_end);
+    !digits.is_empty() && (suffix.is_empty() || matches!(suffix, "k" | "K" | "m" | "M" | "g" | "G"))
+}
+
+/// Computes the `-Xms`/`-Xmx` args from the user's `jvm_initial_heap`/
+/// `jvm_max_heap` settings. `-Xms` is only emitted when configured (the JVM
+/// already picks a sane initial size on its own); `-Xmx` always is, falling
+/// back to [`DEFAULT_MAX_HEAP`] when unset or malformed. An invalid value is
+/// logged and dropped rather than handed to the JVM, which would otherwise
+/// fail jdtls' launch with a cryptic "Invalid initial heap size" error.
+fn heap_args(worktree: &zed::Worktree, quiet: bool, initial_heap: Option<&str>, max_heap: Option<&str>) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(initial_heap) = initial_heap {
+        if looks_like_a_heap_size(initial_heap) {
+            args.push(format!("-Xms{initial_heap}"));
+        } else {
+            crate::log::warn(
+                worktree,
+                quiet,
+                &format!(
+                    "`jvm_initial_heap` {initial_heap:?} doesn't look like a JVM heap size \
+                     (e.g. \"512m\", \"2G\"); ignoring it"
+                ),
+            );
+        }
+    }
+
+    let max_heap = match max_heap {
+        Some(max_heap) if looks_like_a_heap_size(max_heap) => max_heap,
+        Some(max_heap) => {
+            crate::log::warn(
+                worktree,
+                quiet,
+                &format!(
+                    "`jvm_max_heap` {max_heap:?} doesn't look like a JVM heap size \
+                     (e.g. \"512m\", \"2G\"); using the default {DEFAULT_MAX_HEAP} instead"
+                ),
+            );
+            DEFAULT_MAX_HEAP
+        }
+        None => DEFAULT_MAX_HEAP,
+    };
+    args.push(format!("-Xmx{max_heap}"));
+
+    args
+}
+
+/// Flags reserved for jdtls' own launch machinery — letting `jvm_extra_args`
+/// pass one of these through would silently override `-data`'s workspace
+/// isolation or otherwise conflict with an arg this function already
+/// computes. (This extension launches jdtls' native binary directly rather
+/// than `java -jar`, so `-jar` isn't actually in play here, but it's still
+/// rejected in case a user is copying flags from a `java -jar` invocation.)
+const RESERVED_JVM_ARGS: &[&str] = &["-data", "-jar", "-configuration"];
+
+/// Filters `extra_args` against [`RESERVED_JVM_ARGS`], warning about and
+/// dropping any that are reserved rather than passing them through.
+fn filtered_extra_args(worktree: &zed::Worktree, quiet: bool, extra_args: &[String]) -> Vec<String> {
+    extra_args
+        .iter()
+        .filter(|arg| {
+            let reserved = RESERVED_JVM_ARGS.contains(&arg.as_str());
+            if reserved {
+                crate::log::warn(
+                    worktree,
+                    quiet,
+                    &format!("`jvm_extra_args` contains reserved flag {arg:?}; ignoring it"),
+                );
+            }
+            !reserved
+        })
+        .cloned()
+        .collect()
+}
+
+/// Builds jdtls' launch arguments: the Eclipse product/application ids, the
+/// `-Xms`/`-Xmx` heap flags, any user-supplied `jvm_extra_args`, the
+/// `offline` JVM properties (if enabled), and the `-data <path>` flag that
+/// keeps its project index isolated per workspace. When
+/// `launch_args_override` is non-empty, it entirely replaces the computed
+/// product/application args (an escape hatch for setups no individual
+/// config key covers) — the heap, extra, `offline`, and `-data <path>` args
+/// are still appended afterward, since all of them apply regardless of how
+/// the product/application ids were chosen.
+#[allow(clippy::too_many_arguments)]
+pub fn build_jdtls_launch_args(
+    worktree: &zed::Worktree,
+    quiet: bool,
+    workspace_name: Option<&str>,
+    data_dir_hash_length: Option<usize>,
+    jdtls_data_dir: Option<&str>,
+    jdtls_product: Option<&str>,
+    jdtls_application: Option<&str>,
+    launch_args_override: &[String],
+    offline: bool,
+    jvm_initial_heap: Option<&str>,
+    jvm_max_heap: Option<&str>,
+    jvm_extra_args: &[String],
+) -> Vec<String> {
+    if !launch_args_override.is_empty() {
+        crate::log::warn(
+            worktree,
+            quiet,
+            "`jdtls_launch_args_override` is set: replacing jdtls' computed launch args entirely. \
+             This bypasses version gating and is unsupported.",
+        );
+    }
+
+    let mut args = product_and_application_args(jdtls_product, jdtls_application, launch_args_override);
+    args.extend(heap_args(worktree, quiet, jvm_initial_heap, jvm_max_heap));
+    args.extend(filtered_extra_args(worktree, quiet, jvm_extra_args));
+    append_offline_args(&mut args, offline);
+    args.push("-data".to_string());
+    args.push(get_jdtls_data_path(worktree, workspace_name, data_dir_hash_length, jdtls_data_dir));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_check_once() {
+        assert_eq!(CheckUpdates::default(), CheckUpdates::Once);
+    }
+
+    #[test]
+    fn trims_crlf_from_latest_version_build() {
+        let raw = "jdt-language-server-1.46.1-202504011455.tar.gz\r\n";
+        assert_eq!(
+            build_directory_for(raw),
+            "jdt-language-server-1.46.1-202504011455"
+        );
+    }
+
+    #[test]
+    fn strips_full_tar_gz_suffix() {
+        // The extracted directory name must match the archive's top-level
+        // directory exactly, with no leftover trailing `.`.
+        let raw = "jdt-language-server-1.46.1-202504011455.tar.gz";
+        assert_eq!(
+            build_directory_for(raw),
+            "jdt-language-server-1.46.1-202504011455"
+        );
+    }
+
+    #[test]
+    fn accepts_a_captured_latest_txt_response() {
+        // A real `latest.txt` body: a bare filename, no wrapping JSON or HTML.
+        assert!(looks_like_a_jdtls_tarball_filename(
+            "jdt-language-server-1.46.1-202504011455.tar.gz"
+        ));
+    }
+
+    #[test]
+    fn rejects_an_html_error_page_as_latest_txt() {
+        assert!(!looks_like_a_jdtls_tarball_filename(
+            "<html><body>502 Bad Gateway</body></html>"
+        ));
+        assert!(!looks_like_a_jdtls_tarball_filename(""));
+    }
+
+    #[test]
+    fn resolves_a_relative_data_dir_override_against_worktree_root() {
+        assert_eq!(
+            resolve_data_dir_override("jdtls-data", None, "/repo"),
+            "/repo/jdtls-data"
+        );
+    }
+
+    #[test]
+    fn leaves_an_absolute_data_dir_override_unresolved() {
+        assert_eq!(
+            resolve_data_dir_override("/var/cache/jdtls", None, "/repo"),
+            "/var/cache/jdtls"
+        );
+    }
+
+    #[test]
+    fn expands_a_tilde_data_dir_override_against_home() {
+        assert_eq!(
+            resolve_data_dir_override("~/jdtls-data", Some("/home/alice"), "/repo"),
+            "/home/alice/jdtls-data"
+        );
+    }
+
+    #[test]
+    fn truncates_data_dir_hash_to_requested_length() {
+        let full = get_jdtls_data_path_for_key("my-workspace", None);
+        let truncated = get_jdtls_data_path_for_key("my-workspace", Some(6));
+        assert_eq!(truncated.len(), DATA_DIR.len() + 1 + 6);
+        assert!(full.starts_with(&truncated));
+    }
+
+    #[test]
+    fn falls_back_to_default_product_and_application_when_unset() {
+        assert_eq!(non_empty_or_default(None, DEFAULT_JDTLS_PRODUCT), DEFAULT_JDTLS_PRODUCT);
+        assert_eq!(non_empty_or_default(Some("  "), DEFAULT_JDTLS_PRODUCT), DEFAULT_JDTLS_PRODUCT);
+    }
+
+    #[test]
+    fn honors_a_custom_product_override() {
+        assert_eq!(non_empty_or_default(Some("com.acme.jdtls.product"), DEFAULT_JDTLS_PRODUCT), "com.acme.jdtls.product");
+    }
+
+    #[test]
+    fn install_fingerprint_changes_when_pinned_product_changes() {
+        let before = install_fingerprint(false, None, None);
+        let after = install_fingerprint(false, Some("com.acme.jdtls.product"), None);
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn install_fingerprint_stable_for_identical_settings() {
+        let a = install_fingerprint(true, Some("com.acme.jdtls.product"), Some("com.acme.jdtls.app"));
+        let b = install_fingerprint(true, Some("com.acme.jdtls.product"), Some("com.acme.jdtls.app"));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn computes_product_and_application_args_when_no_override() {
+        assert_eq!(
+            product_and_application_args(None, None, &[]),
+            vec![
+                format!("-Declipse.product={DEFAULT_JDTLS_PRODUCT}"),
+                format!("-Declipse.application={DEFAULT_JDTLS_APPLICATION}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_valid_heap_sizes() {
+        assert!(looks_like_a_heap_size("512m"));
+        assert!(looks_like_a_heap_size("4G"));
+        assert!(looks_like_a_heap_size("1048576"));
+    }
+
+    #[test]
+    fn rejects_malformed_heap_sizes() {
+        assert!(!looks_like_a_heap_size(""));
+        assert!(!looks_like_a_heap_size("4GB"));
+        assert!(!looks_like_a_heap_size("plenty"));
+        assert!(!looks_like_a_heap_size("-4G"));
+    }
+
+    #[test]
+    fn appends_offline_args_when_enabled() {
+        let mut args = vec!["-Declipse.product=x".to_string()];
+        append_offline_args(&mut args, true);
+        assert_eq!(args, vec!["-Declipse.product=x".to_string(), "-Declipse.p2.unsignedPolicy=allow".to_string()]);
+    }
+
+    #[test]
+    fn omits_offline_args_when_disabled() {
+        let mut args = vec!["-Declipse.product=x".to_string()];
+        append_offline_args(&mut args, false);
+        assert_eq!(args, vec!["-Declipse.product=x".to_string()]);
+    }
+
+    #[test]
+    fn override_replaces_product_and_application_args_verbatim() {
+        let override_args = vec!["-Dsome.custom.flag=true".to_string()];
+        assert_eq!(
+            product_and_application_args(Some("ignored.product"), Some("ignored.application"), &override_args),
+            override_args
+        );
+    }
+
+    #[test]
+    fn ignores_hash_length_at_or_above_full_length() {
+        let full = get_jdtls_data_path_for_key("my-workspace", None);
+        let unchanged = get_jdtls_data_path_for_key("my-workspace", Some(FULL_HASH_LENGTH));
+        assert_eq!(full, unchanged);
+    }
+
+    fn get_jdtls_data_path_for_key(key: &str, hash_length: Option<usize>) -> String {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let full_hash = format!("{:x}", hasher.finish());
+        let truncated = match hash_length {
+            Some(len) if len < full_hash.len() => &full_hash[..len],
+            _ => full_hash.as_str(),
+        };
+        format!("{DATA_DIR}/{truncated}")
+    }
+
+    #[test]
+    fn picks_the_child_directory_that_actually_has_the_binary() {
+        // Simulates a mismatched archive layout: the tarball's real
+        // top-level directory doesn't match the name derived from
+        // `latest.txt`, so `bin/jdtls` shows up one level deeper than
+        // expected, under an unrelated sibling like `README.md`.
+        let children = [("README.md", false), ("jdt-language-server-1.46.1-202504011455", true)];
+        assert_eq!(
+            pick_nested_directory(&children),
+            Some("jdt-language-server-1.46.1-202504011455")
+        );
+    }
+
+    #[test]
+    fn no_nested_directory_found_when_none_has_the_binary() {
+        let children = [("README.md", false), ("LICENSE", false)];
+        assert_eq!(pick_nested_directory(&children), None);
+    }
+
+    #[test]
+    fn top_level_component_is_unchanged_for_a_flat_build_directory() {
+        assert_eq!(
+            top_level_component("jdt-language-server-1.46.1-202504011455"),
+            "jdt-language-server-1.46.1-202504011455"
+        );
+    }
+
+    #[test]
+    fn top_level_component_drops_the_nested_segment() {
+        assert_eq!(
+            top_level_component("jdt-language-server-1.46.1-202504011455/nested"),
+            "jdt-language-server-1.46.1-202504011455"
+        );
+    }
+
+    #[test]
+    fn detects_the_windows_batch_launcher() {
+        assert!(is_windows_batch_launcher("./.jdtls/bin/jdtls.bat"));
+        assert!(!is_windows_batch_launcher("./.jdtls/bin/jdtls"));
+        assert!(!is_windows_batch_launcher(".jdtls/jdt-language-server-1.46.1/bin/jdtls"));
+    }
+
+    #[test]
+    fn wraps_the_javaagent_flag_for_the_bat_launcher() {
+        assert_eq!(
+            jvm_arg("./.jdtls/bin/jdtls.bat", "-javaagent:.lombok/lombok.jar"),
+            "--jvm-arg=-javaagent:.lombok/lombok.jar"
+        );
+    }
+
+    #[test]
+    fn leaves_the_javaagent_flag_untouched_for_the_shell_launcher() {
+        assert_eq!(
+            jvm_arg("./.jdtls/bin/jdtls", "-javaagent:.lombok/lombok.jar"),
+            "-javaagent:.lombok/lombok.jar"
+        );
+    }
+}
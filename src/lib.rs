@@ -0,0 +1,17 @@
+mod completion;
+mod config;
+mod debug_config;
+mod debugger;
+mod java;
+mod java_info;
+mod jdk;
+mod jdtls;
+mod log;
+mod lombok;
+mod lsp;
+mod runtime;
+mod util;
+
+use java::JavaExtension;
+
+zed_extension_api::register_extension!(JavaExtension);
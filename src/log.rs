@@ -0,0 +1,51 @@
+use zed_extension_api::{self as zed};
+
+/// Emits a warning line to Zed's extension log, honoring `NO_COLOR` (per
+/// https://no-color.org) and the extension's own `quiet` setting.
+pub fn warn(worktree: &zed::Worktree, quiet: bool, message: &str) {
+    if quiet {
+        return;
+    }
+
+    if no_color(worktree) {
+        println!("java: {message}");
+    } else {
+        println!("\x1b[33mjava: {message}\x1b[0m");
+    }
+}
+
+/// Emits an informational line, for routine confirmations (e.g. "cleared
+/// the update-check cache") that don't warrant a warning's attention.
+pub fn info(worktree: &zed::Worktree, quiet: bool, message: &str) {
+    if quiet {
+        return;
+    }
+
+    if no_color(worktree) {
+        println!("java: {message}");
+    } else {
+        println!("\x1b[36mjava: {message}\x1b[0m");
+    }
+}
+
+/// Emits a verbose diagnostic line (e.g. per-download progress), for detail
+/// that's only worth printing when a user has opted into `verbose_logging`
+/// to chase down a support issue — routine confirmations that matter on
+/// every run belong in [`info`] instead.
+pub fn debug(worktree: &zed::Worktree, quiet: bool, verbose: bool, message: &str) {
+    if quiet || !verbose {
+        return;
+    }
+
+    if no_color(worktree) {
+        println!("java (debug): {message}");
+    } else {
+        println!("\x1b[90mjava (debug): {message}\x1b[0m");
+    }
+}
+
+fn no_color(worktree: &zed::Worktree) -> bool {
+    crate::util::shell_env_var(worktree, "NO_COLOR")
+        .map(|value| !value.is_empty())
+        .unwrap_or(false)
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use zed_extension_api::{self as zed, DownloadedFileType, Result};
+
+use crate::util;
+
+const INSTALL_DIR: &str = ".lombok";
+const LOMBOK_URL: &str = "https://projectlombok.org/downloads/lombok.jar";
+
+/// Whether lombok's annotation processing is enabled in jdtls (`java.jdt.ls.lombokSupport.enabled`)
+/// and whether jdtls itself is launched with `-javaagent:lombok.jar`. These
+/// are independent: a project can want lombok-aware completions/diagnostics
+/// without patching jdtls' own class loading.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LombokSettings {
+    pub lombok_support: bool,
+    pub lombok_agent: bool,
+    /// Path to an already-installed `lombok.jar`, for users whose build
+    /// already provides one. When set, `ensure_installed` uses it as-is
+    /// instead of downloading a copy to [`INSTALL_DIR`].
+    pub lombok_jar_path: Option<String>,
+}
+
+impl Default for LombokSettings {
+    fn default() -> Self {
+        Self {
+            lombok_support: true,
+            lombok_agent: true,
+            lombok_jar_path: None,
+        }
+    }
+}
+
+/// Resolves the lombok jar to hand to jdtls, unless the agent is disabled
+/// entirely. If `lombok_jar_path` is set, that file is validated and used
+/// as-is; otherwise `lombok.jar` is downloaded to [`INSTALL_DIR`] on first
+/// use.
+pub fn ensure_installed(
+    language_server_id: &zed::LanguageServerId,
+    settings: &LombokSettings,
+    download_mirror: Option<&str>,
+) -> Result<Option<String>> {
+    if !settings.lombok_agent {
+        return Ok(None);
+    }
+
+    if let Some(jar_path) = settings.lombok_jar_path.as_deref() {
+        util::verify_existing_jar(jar_path)
+            .map_err(|err| format!("lombok_jar_path {jar_path} is unusable: {err}"))?;
+        return Ok(Some(jar_path.to_string()));
+    }
+
+    util::create_path_if_not_exists(INSTALL_DIR)?;
+    let jar_path = lombok_jar_path();
+
+    if !Path::new(&jar_path).exists() {
+        try_to_fetch_and_install_latest_lombok(language_server_id, &jar_path, download_mirror)?;
+    }
+
+    Ok(Some(jar_path))
+}
+
+pub fn lombok_jar_path() -> String {
+    format!("{INSTALL_DIR}/lombok.jar")
+}
+
+fn try_to_fetch_and_install_latest_lombok(
+    language_server_id: &zed::LanguageServerId,
+    jar_path: &str,
+    download_mirror: Option<&str>,
+) -> Result<()> {
+    zed::set_language_server_installation_status(
+        language_server_id,
+        &zed::LanguageServerInstallationStatus::Downloading,
+    );
+
+    let url = util::apply_download_mirror(LOMBOK_URL, download_mirror);
+    util::retry_with_backoff(util::is_transient_fetch_error, || {
+        zed::download_file(&url, jar_path, DownloadedFileType::Uncompressed)
+            .map_err(|err| format!("failed to download lombok.jar: {err}"))
+    })?;
+
+    util::verify_jar_magic(jar_path)
+        .map_err(|err| format!("downloaded lombok.jar from {url} is corrupt: {err}"))
+}
+
+/// Builds the `-javaagent:` flag for jdtls' launch args, or `None` when the
+/// agent is disabled (lombok support can still be enabled in jdtls itself
+/// via `lombok_support`, just without patching jdtls' own class loading).
+pub fn javaagent_arg(jar_path: &str, settings: &LombokSettings) -> Option<String> {
+    settings.lombok_agent.then(|| format!("-javaagent:{jar_path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_agent_and_support_enabled() {
+        let settings = LombokSettings::default();
+        assert!(settings.lombok_support);
+        assert!(settings.lombok_agent);
+    }
+
+    #[test]
+    fn no_javaagent_when_disabled() {
+        let settings = LombokSettings {
+            lombok_agent: false,
+            ..Default::default()
+        };
+        assert_eq!(javaagent_arg("lombok.jar", &settings), None);
+    }
+
+    #[test]
+    fn javaagent_when_enabled() {
+        let settings = LombokSettings::default();
+        assert_eq!(
+            javaagent_arg("lombok.jar", &settings),
+            Some("-javaagent:lombok.jar".to_string())
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use zed_extension_api::Result;
+
+// There is no `proxy.mjs`/port-file bridge in this codebase to replace with a
+// pure-Rust `workspace/executeCommand` path — jdtls is launched directly (see
+// the module doc comment at the top of `java.rs`), and this extension API
+// version gives extensions no way to dispatch an arbitrary LSP request/command
+// themselves (see `resolve_main_class`'s doc comment below). Once that
+// capability exists, `MainClassCache::get_or_resolve` is the natural place to
+// call it from — including a `mainClass`-disambiguation path for a debug
+// launch config, which has nowhere to live until this extension can actually
+// call `vscode.java.resolveMainClass` and discover there's more than one
+// candidate to disambiguate between. There's consequently no port file here
+// either — no retry loop to harden — since there's no proxy process writing
+// one for this extension to read.
+
+/// One candidate produced by jdtls' `vscode.java.resolveMainClass` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MainClassEntry {
+    pub main_class: String,
+    pub project_name: String,
+    pub file_path: String,
+}
+
+/// Filters `resolveMainClass` results down to a specific project. jdtls
+/// reports an empty (or `"<default>"`) project name for single-file runs
+/// and default-package mains that were never associated with a real
+/// Maven/Gradle project — when the debug config didn't request a specific
+/// project, those entries must not be filtered out, or running a bare
+/// `Main.java` with no build file would never resolve anything.
+///
+/// Not called yet — nothing in this crate can issue the
+/// `vscode.java.resolveMainClass` command these entries would come from
+/// (see the module doc comment above).
+#[allow(dead_code)]
+pub fn resolve_main_class<'a>(
+    entries: &'a [MainClassEntry],
+    project_name: Option<&str>,
+) -> Vec<&'a MainClassEntry> {
+    match project_name {
+        Some(name) => entries.iter().filter(|entry| entry.project_name == name).collect(),
+        None => entries.iter().collect(),
+    }
+}
+
+/// Caches the result of `resolveMainClass` so repeated debug starts in the
+/// same session don't re-run jdtls' (relatively slow) project-wide main
+/// class scan every time.
+#[derive(Default)]
+pub struct MainClassCache {
+    cached: Option<Vec<MainClassEntry>>,
+}
+
+impl MainClassCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached main class list, resolving it via `resolve` on a
+    /// cache miss.
+    ///
+    /// Not called yet — same as [`resolve_main_class`], waiting on a way to
+    /// actually run `resolveMainClass` to call it with.
+    #[allow(dead_code)]
+    pub fn get_or_resolve(
+        &mut self,
+        resolve: impl FnOnce() -> Result<Vec<MainClassEntry>>,
+    ) -> Result<&[MainClassEntry]> {
+        if self.cached.is_none() {
+            self.cached = Some(resolve()?);
+        }
+        Ok(self.cached.as_deref().unwrap())
+    }
+
+    /// Drops the cache, e.g. after a build or a source edit that could
+    /// change which classes have a `main` method.
+    pub fn invalidate(&mut self) {
+        self.cached = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_resolves_once_until_invalidated() {
+        let mut cache = MainClassCache::new();
+        let mut resolve_calls = 0;
+
+        let entry = MainClassEntry {
+            main_class: "com.example.Main".to_string(),
+            project_name: "app".to_string(),
+            file_path: "src/main/java/com/example/Main.java".to_string(),
+        };
+
+        for _ in 0..3 {
+            cache
+                .get_or_resolve(|| {
+                    resolve_calls += 1;
+                    Ok(vec![entry.clone()])
+                })
+                .unwrap();
+        }
+        assert_eq!(resolve_calls, 1);
+
+        cache.invalidate();
+        cache
+            .get_or_resolve(|| {
+                resolve_calls += 1;
+                Ok(vec![entry.clone()])
+            })
+            .unwrap();
+        assert_eq!(resolve_calls, 2);
+    }
+
+    #[test]
+    fn includes_default_package_entry_when_no_project_requested() {
+        let entries = vec![MainClassEntry {
+            main_class: "Main".to_string(),
+            project_name: String::new(),
+            file_path: "Main.java".to_string(),
+        }];
+        assert_eq!(resolve_main_class(&entries, None), vec![&entries[0]]);
+    }
+
+    #[test]
+    fn filters_by_requested_project_name() {
+        let entries = vec![
+            MainClassEntry {
+                main_class: "com.example.Main".to_string(),
+                project_name: "app".to_string(),
+                file_path: "app/Main.java".to_string(),
+            },
+            MainClassEntry {
+                main_class: "com.example.Other".to_string(),
+                project_name: "lib".to_string(),
+                file_path: "lib/Other.java".to_string(),
+            },
+        ];
+        assert_eq!(
+            resolve_main_class(&entries, Some("app")),
+            vec![&entries[0]]
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use serde::Deserialize;
+use zed_extension_api::{self as zed};
+
+/// A user-declared JDK the project can compile against, keyed by the
+/// `java.configuration.runtimes` `name` convention (e.g. `"JavaSE-17"`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct RuntimeConfig {
+    pub name: String,
+    pub path: String,
+    /// Marks this entry as jdtls' `java.configuration.runtimes[].default`,
+    /// the runtime used for projects with no detected/configured source
+    /// level. At most one entry should set this; jdtls itself decides what
+    /// happens if more than one does.
+    pub default: bool,
+}
+
+/// Reads the project's declared source level from `pom.xml`
+/// (`maven.compiler.source`) or `build.gradle`/`build.gradle.kts`
+/// (`sourceCompatibility`), if present.
+pub fn detect_project_source_level(worktree: &zed::Worktree) -> Option<String> {
+    if let Ok(pom) = worktree.read_text_file("pom.xml") {
+        if let Some(source) = extract_tag_value(&pom, "maven.compiler.source") {
+            return Some(source);
+        }
+    }
+
+    for build_file in ["build.gradle", "build.gradle.kts"] {
+        if let Ok(build) = worktree.read_text_file(build_file) {
+            if let Some(source) = extract_gradle_source_compatibility(&build) {
+                return Some(source);
+            }
+        }
+    }
+
+    None
+}
+
+fn extract_tag_value(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+    Some(xml[start..end].trim().to_string())
+}
+
+fn extract_gradle_source_compatibility(build: &str) -> Option<String> {
+    build.lines().find_map(|line| {
+        let line = line.trim();
+        let rest = line.strip_prefix("sourceCompatibility")?;
+        Some(
+            rest.trim()
+                .trim_start_matches('=')
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string(),
+        )
+    })
+}
+
+/// `~/.sdkman/candidates/java/current`'s target, if sdkman is installed and
+/// `current` points at something that looks like a real JDK. Returns `None`
+/// (rather than an error) on any failure — a missing or broken sdkman
+/// install should just fall through to the next `java_home` resolution
+/// tier, not block startup.
+pub fn resolve_sdkman_java_home(worktree: &zed::Worktree) -> Option<String> {
+    let home = crate::util::shell_env_var(worktree, "HOME")?;
+    let current = std::path::Path::new(&home).join(".sdkman/candidates/java/current");
+    let resolved = std::fs::canonicalize(&current).ok()?;
+    is_jdk_home(&resolved).then(|| resolved.to_string_lossy().into_owned())
+}
+
+/// A directory "looks like a JDK" if it has a `bin/java` executable —
+/// sdkman's `current` symlink can point at a half-removed or in-progress
+/// candidate install, so this is checked rather than trusting the symlink
+/// blindly.
+fn is_jdk_home(path: &std::path::Path) -> bool {
+    path.join("bin").join("java").exists()
+}
+
+/// Whether the worktree root looks like a Gradle project (as opposed to
+/// Maven or a loose set of source files). Gradle's import can take a while
+/// on a freshly opened project, so callers surfacing an error about
+/// classpath/debug state can use this to hint that an import may still be
+/// in progress rather than something being actually broken — the real
+/// `zed::Extension` trait has no debug-adapter hook this extension could use
+/// to retry a `resolveClasspath`-style request against jdtls instead, so a
+/// diagnostic hint is the extent of what's wireable today.
+pub fn is_gradle_project(worktree: &zed::Worktree) -> bool {
+    worktree.read_text_file("build.gradle").is_ok() || worktree.read_text_file("build.gradle.kts").is_ok()
+}
+
+/// The minimum JDK major version jdtls itself requires to run, independent
+/// of whatever JDK the project compiles against.
+pub const JDTLS_MINIMUM_JAVA_VERSION: u32 = 21;
+
+/// Reads the project's pinned `--release` version from `pom.xml`
+/// (`maven.compiler.release`) or `build.gradle`/`build.gradle.kts`
+/// (`options.release`), if present.
+pub fn detect_configured_release(worktree: &zed::Worktree) -> Option<u32> {
+    if let Ok(pom) = worktree.read_text_file("pom.xml") {
+        if let Some(release) = extract_tag_value(&pom, "maven.compiler.release") {
+            return release.parse().ok();
+        }
+    }
+
+    for build_file in ["build.gradle", "build.gradle.kts"] {
+        if let Ok(build) = worktree.read_text_file(build_file) {
+            if let Some(release) = build.lines().find_map(|line| {
+                let line = line.trim();
+                let rest = line.strip_prefix("options.release")?;
+                rest.trim_start_matches(['=', '.'])
+                    .trim_start_matches("set(")
+                    .trim_matches(|c: char| c == ')' || c == '"' || c == '\'' || c.is_whitespace())
+                    .parse()
+                    .ok()
+            }) {
+                return Some(release);
+            }
+        }
+    }
+
+    None
+}
+
+/// Maps a raw source-level string (`"17"`, `"1.8"`, `"JavaSE-21"`) onto the
+/// `java.configuration.runtimes` `name` it corresponds to, then finds the
+/// matching configured runtime. Returns `None` (and the caller should warn)
+/// if the project's source level has no configured runtime.
+pub fn select_compiler_runtime<'a>(
+    source_level: &str,
+    runtimes: &'a [RuntimeConfig],
+) -> Option<&'a RuntimeConfig> {
+    let normalized = normalize_source_level(source_level);
+    runtimes.iter().find(|runtime| runtime.name == normalized)
+}
+
+fn normalize_source_level(source_level: &str) -> String {
+    let source_level = source_level.trim();
+    if let Some(version) = source_level.strip_prefix("JavaSE-") {
+        return format!("JavaSE-{version}");
+    }
+    let version = source_level.trim_start_matches("1.");
+    format!("JavaSE-{version}")
+}
+
+/// Resolves `java_home` when it names a configured runtime alias instead of
+/// a literal path — a bare version (`"21"`) or `java.configuration.runtimes`
+/// name (`"JavaSE-21"`) — using the same `JavaSE-N` normalization
+/// `select_compiler_runtime` applies to project source levels. A value that
+/// looks like a filesystem path (contains `/` or `\`) is returned unchanged
+/// without consulting `runtimes` at all; an alias with no matching runtime
+/// also falls back to the raw value, so a typo surfaces as jdtls failing to
+/// find that path rather than a silent alias-resolution error here.
+pub fn get_java_home(java_home: &str, runtimes: &[RuntimeConfig]) -> String {
+    if looks_like_path(java_home) {
+        return java_home.to_string();
+    }
+    let normalized = normalize_source_level(java_home);
+    runtimes
+        .iter()
+        .find(|runtime| runtime.name == normalized)
+        .map(|runtime| runtime.path.clone())
+        .unwrap_or_else(|| java_home.to_string())
+}
+
+fn looks_like_path(value: &str) -> bool {
+    value.contains('/') || value.contains('\\')
+}
+
+/// `javac --release N` cross-compiles against an older platform API even
+/// when run on a newer JDK. If the project pins a `--release` version,
+/// jdtls itself can stay on a modern JDK (21+) while still catching uses of
+/// APIs unavailable at that release. Warns when the configured `--release`
+/// is newer than the JDK jdtls is actually running on.
+pub fn warn_if_release_exceeds_runtime(
+    worktree: &zed::Worktree,
+    quiet: bool,
+    release: u32,
+    jdtls_java_major_version: u32,
+) {
+    if release > jdtls_java_major_version {
+        crate::log::warn(
+            worktree,
+            quiet,
+            &format!(
+                "project compiles with `--release {release}` but jdtls is running on \
+                 JDK {jdtls_java_major_version}, which cannot target a newer release; \
+                 configure a JDK {release}+ runtime for jdtls"
+            ),
+        );
+    }
+}
+
+/// Warns when the JDK jdtls is actually about to run on is older than
+/// [`JDTLS_MINIMUM_JAVA_VERSION`] — distinct from
+/// [`warn_if_release_exceeds_runtime`], which compares against a project's
+/// configured `--release`, not the measured version of a resolved
+/// `java_home`.
+pub fn warn_if_jdtls_jdk_too_old(worktree: &zed::Worktree, quiet: bool, resolved_major_version: u32) {
+    if resolved_major_version < JDTLS_MINIMUM_JAVA_VERSION {
+        crate::log::warn(
+            worktree,
+            quiet,
+            &format!(
+                "resolved `java_home` runs JDK {resolved_major_version}, but jdtls requires \
+                 JDK {JDTLS_MINIMUM_JAVA_VERSION}+; configure a newer `java_home` or enable \
+                 `jdk_auto_download`"
+            ),
+        );
+    }
+}
+
+/// Name of the project file used to check in default JVM args (the
+/// `JAVA_TOOL_OPTIONS` a team wants every contributor's jdtls and debugged
+/// processes to inherit, without relying on each person's shell profile).
+const JAVA_TOOL_OPTIONS_FILE: &str = ".java-tool-options";
+
+/// Reads `.java-tool-options` from the project root, if present, trimmed of
+/// surrounding whitespace. Returns `None` for a missing or empty file.
+pub fn detect_project_java_tool_options(worktree: &zed::Worktree) -> Option<String> {
+    let contents = worktree.read_text_file(JAVA_TOOL_OPTIONS_FILE).ok()?;
+    let trimmed = contents.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// `JAVA_TOOL_OPTIONS` is read by every JVM on startup, including jdtls and
+/// any process it debugs, but nothing about a project makes that obvious —
+/// unlike `.java-tool-options`, which is at least checked into the repo.
+/// Warns so an inherited shell variable doesn't read as mysterious jdtls or
+/// debuggee behavior.
+pub fn warn_if_java_tool_options_env_set(worktree: &zed::Worktree, quiet: bool) {
+    if let Some(value) = crate::util::shell_env_var(worktree, "JAVA_TOOL_OPTIONS") {
+        crate::log::warn(
+            worktree,
+            quiet,
+            &format!(
+                "`JAVA_TOOL_OPTIONS` is set in the environment ({value:?}) and will silently \
+                 affect jdtls and any process it debugs; move it to `.java-tool-options` if it's \
+                 meant to apply to this project"
+            ),
+        );
+    }
+}
+
+pub fn warn_if_no_runtime_configured(
+    worktree: &zed::Worktree,
+    quiet: bool,
+    source_level: &str,
+    runtimes: &[RuntimeConfig],
+) {
+    if select_compiler_runtime(source_level, runtimes).is_none() {
+        crate::log::warn(
+            worktree,
+            quiet,
+            &format!(
+                "project declares source level {source_level:?} but no matching entry \
+                 was found in `runtimes`; jdtls will compile with its own JDK instead"
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_source_levels() {
+        assert_eq!(normalize_source_level("17"), "JavaSE-17");
+        assert_eq!(normalize_source_level("1.8"), "JavaSE-8");
+        assert_eq!(normalize_source_level("JavaSE-21"), "JavaSE-21");
+    }
+
+    #[test]
+    fn extracts_maven_source_level() {
+        let pom = "<project><properties><maven.compiler.source>17</maven.compiler.source></properties></project>";
+        assert_eq!(
+            extract_tag_value(pom, "maven.compiler.source"),
+            Some("17".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_maven_release_tag() {
+        let pom = "<properties><maven.compiler.release>17</maven.compiler.release></properties>";
+        assert_eq!(
+            extract_tag_value(pom, "maven.compiler.release").and_then(|v| v.parse::<u32>().ok()),
+            Some(17)
+        );
+    }
+
+    #[test]
+    fn extracts_gradle_source_compatibility() {
+        let build = "group = 'com.example'\nsourceCompatibility = '17'\n";
+        assert_eq!(
+            extract_gradle_source_compatibility(build),
+            Some("17".to_string())
+        );
+    }
+
+    #[test]
+    fn java_tool_options_file_name_has_no_leading_slash() {
+        // `worktree.read_text_file` resolves relative to the project root, so
+        // this must stay a bare filename rather than an absolute path.
+        assert!(!JAVA_TOOL_OPTIONS_FILE.starts_with('/'));
+    }
+
+    #[test]
+    fn selects_matching_runtime() {
+        let runtimes = vec![RuntimeConfig {
+            name: "JavaSE-17".into(),
+            path: "/opt/jdk-17".into(),
+            default: false,
+        }];
+        assert!(select_compiler_runtime("17", &runtimes).is_some());
+        assert!(select_compiler_runtime("21", &runtimes).is_none());
+    }
+
+    #[test]
+    fn resolves_bare_version_java_home_against_runtimes() {
+        let runtimes = vec![RuntimeConfig {
+            name: "JavaSE-21".into(),
+            path: "/opt/jdk-21".into(),
+            default: false,
+        }];
+        assert_eq!(get_java_home("21", &runtimes), "/opt/jdk-21");
+    }
+
+    #[test]
+    fn resolves_runtime_name_java_home_against_runtimes() {
+        let runtimes = vec![RuntimeConfig {
+            name: "JavaSE-21".into(),
+            path: "/opt/jdk-21".into(),
+            default: false,
+        }];
+        assert_eq!(get_java_home("JavaSE-21", &runtimes), "/opt/jdk-21");
+    }
+
+    #[test]
+    fn leaves_a_literal_path_java_home_untouched() {
+        let runtimes = vec![RuntimeConfig {
+            name: "JavaSE-21".into(),
+            path: "/opt/jdk-21".into(),
+            default: false,
+        }];
+        assert_eq!(get_java_home("/usr/lib/jvm/jdk-17", &runtimes), "/usr/lib/jvm/jdk-17");
+    }
+
+    #[test]
+    fn falls_back_to_raw_value_when_alias_has_no_matching_runtime() {
+        assert_eq!(get_java_home("21", &[]), "21");
+    }
+}
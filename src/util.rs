@@ -0,0 +1,741 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+use zed_extension_api::{self as zed, Result};
+
+/// User-Agent sent with every outbound HTTP request unless overridden by
+/// the `http_user_agent` setting. GitHub (and some Eclipse/Adoptium
+/// mirrors) reject or rate-limit requests with no User-Agent at all.
+pub const DEFAULT_USER_AGENT: &str = concat!("zed-java-extension/", env!("CARGO_PKG_VERSION"));
+
+/// Resolves the effective User-Agent: the user's override if set, else
+/// [`DEFAULT_USER_AGENT`].
+pub fn resolve_user_agent(override_agent: Option<&str>) -> &str {
+    override_agent.unwrap_or(DEFAULT_USER_AGENT)
+}
+
+/// Result of checking a JSON pointer against an expected shape: distinct
+/// from a plain `Option`, so callers can tell "not configured" (fine, use
+/// the default) apart from "configured, but the wrong shape" (a
+/// misconfiguration worth surfacing rather than silently falling back).
+pub enum PointerCheck {
+    Absent,
+    WrongType { found: &'static str },
+    Ok,
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Checks that `pointer` (RFC 6901 syntax, e.g. `/debug/hot_code_replace`)
+/// either doesn't exist in `value`, or exists and satisfies `expected`.
+pub fn check_pointer_shape(value: &Value, pointer: &str, expected: impl Fn(&Value) -> bool) -> PointerCheck {
+    match value.pointer(pointer) {
+        None => PointerCheck::Absent,
+        Some(found) if expected(found) => PointerCheck::Ok,
+        Some(found) => PointerCheck::WrongType {
+            found: json_type_name(found),
+        },
+    }
+}
+
+/// Recursively merges `overlay` into `base`: nested objects are merged key
+/// by key, everything else (including arrays) is replaced wholesale by
+/// `overlay`'s value when present.
+pub fn deep_merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base_map), Value::Object(overlay_map)) => {
+            for (key, overlay_value) in overlay_map {
+                deep_merge(
+                    base_map.entry(key.clone()).or_insert(Value::Null),
+                    overlay_value,
+                );
+            }
+        }
+        (base, overlay) => {
+            *base = overlay.clone();
+        }
+    }
+}
+
+/// Walks `root`, creating intermediate JSON objects as needed, and returns
+/// the object at `path`. Used to merge extension settings into the nested
+/// `java.*` workspace configuration jdtls expects.
+pub fn json_object_mut<'a>(root: &'a mut Value, path: &[&str]) -> Result<&'a mut Map<String, Value>> {
+    let mut current = root
+        .as_object_mut()
+        .ok_or_else(|| "expected a JSON object".to_string())?;
+
+    for segment in path {
+        current = current
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| format!("expected `{segment}` to be a JSON object"))?;
+    }
+
+    Ok(current)
+}
+
+/// Rewrites `url` to route through `mirror` (a corporate proxy/Nexus-style
+/// raw repository that re-hosts arbitrary upstream URLs under its own base),
+/// by appending `url` (minus its scheme) onto `mirror`. Returns `url`
+/// unchanged when no mirror is configured. E.g. with
+/// `mirror = "https://nexus.corp.example/repository/raw-proxy"`, a jdtls
+/// download from `https://download.eclipse.org/jdtls/milestones/latest/x.tar.gz`
+/// becomes `https://nexus.corp.example/repository/raw-proxy/download.eclipse.org/jdtls/milestones/latest/x.tar.gz`.
+pub fn apply_download_mirror(url: &str, mirror: Option<&str>) -> String {
+    match mirror {
+        Some(mirror) => {
+            let mirror = mirror.trim_end_matches('/');
+            let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+            format!("{mirror}/{without_scheme}")
+        }
+        None => url.to_string(),
+    }
+}
+
+/// Expands a leading `~` to the user's home directory. Paths without a
+/// leading `~` are returned unchanged.
+pub fn expand_home_path(path: &str, home: Option<&str>) -> String {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = home {
+            return format!("{home}/{rest}");
+        }
+    }
+    path.to_string()
+}
+
+/// Reads `key` from the worktree's shell environment, falling back to the
+/// extension process' own environment. `shell_env()` can come back empty
+/// (e.g. a worktree whose shell hasn't finished initializing yet), in which
+/// case callers would otherwise silently lose values like `HOME` or
+/// `NO_COLOR` that are almost always set on the process itself.
+pub fn shell_env_var(worktree: &zed::Worktree, key: &str) -> Option<String> {
+    worktree
+        .shell_env()
+        .into_iter()
+        .find_map(|(k, v)| (k == key).then_some(v))
+        .or_else(|| std::env::var(key).ok())
+}
+
+/// Creates `path` (and any missing parent directories) if it doesn't already
+/// exist. A no-op if the directory is already present.
+pub fn create_path_if_not_exists(path: &str) -> Result<()> {
+    if !Path::new(path).exists() {
+        fs::create_dir_all(path).map_err(|err| format!("failed to create directory {path}: {err}"))?;
+    }
+    Ok(())
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ZIP_MAGIC: [u8; 2] = [b'P', b'K'];
+
+/// Rejects a downloaded archive body that doesn't start with the magic
+/// bytes for its declared type. Eclipse/Corretto occasionally return an
+/// HTML error page (rate limit, 404) with a `200` status, which would
+/// otherwise be saved as-is and fail with a confusing extraction error.
+fn verify_archive_magic(body: &[u8], file_type: zed::DownloadedFileType) -> Result<()> {
+    let expected: &[u8] = match file_type {
+        zed::DownloadedFileType::GzipTar | zed::DownloadedFileType::Gzip => &GZIP_MAGIC,
+        zed::DownloadedFileType::Zip => &ZIP_MAGIC,
+        zed::DownloadedFileType::Uncompressed => return Ok(()),
+    };
+
+    if !body.starts_with(expected) {
+        return Err("download returned an HTML error page, not an archive".to_string());
+    }
+
+    Ok(())
+}
+
+/// A jar is a zip archive, so a real one starts with the local-file-header
+/// signature `PK\x03\x04`.
+const JAR_MAGIC: [u8; 4] = [0x50, 0x4B, 0x03, 0x04];
+
+/// Checks that the file at `path` starts with [`JAR_MAGIC`], deleting it and
+/// returning an error if not. Used right after downloading `lombok.jar`,
+/// which (unlike the jdtls/JDK archives) is saved straight to disk via
+/// `zed::download_file` rather than fetched into memory first, so there's no
+/// in-memory body for `verify_archive_magic` to check before it's written —
+/// this instead reads back just the first few bytes of what landed on disk.
+/// An HTML error page served with a `200` status would otherwise be cached
+/// and handed to jdtls as `-javaagent:lombok.jar`, breaking the whole LSP.
+pub fn verify_jar_magic(path: &str) -> Result<()> {
+    if let Err(err) = check_jar_magic(path) {
+        let _ = fs::remove_file(path);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Checks that `path` exists and starts with [`JAR_MAGIC`], without
+/// deleting it on failure. Used to validate a user-provided jar override
+/// (e.g. `lombok_jar_path`), where the file is the user's own and isn't
+/// ours to remove.
+pub fn verify_existing_jar(path: &str) -> Result<()> {
+    if !Path::new(path).exists() {
+        return Err(format!("{path} does not exist"));
+    }
+
+    check_jar_magic(path)
+}
+
+fn check_jar_magic(path: &str) -> Result<()> {
+    use std::io::Read;
+
+    let mut header = [0u8; JAR_MAGIC.len()];
+    let read = fs::File::open(path)
+        .and_then(|mut file| file.read(&mut header))
+        .map_err(|err| format!("failed to read {path}: {err}"))?;
+
+    if read < JAR_MAGIC.len() || header != JAR_MAGIC {
+        return Err(format!("{path} doesn't look like a jar (missing the PK\\x03\\x04 zip signature)"));
+    }
+
+    Ok(())
+}
+
+/// Attempts, including the first, [`retry_with_backoff`] makes before giving
+/// up.
+const MAX_FETCH_ATTEMPTS: u32 = 3;
+
+/// Base delay between retries, doubled on each subsequent attempt (500ms,
+/// 1s, 2s, ...).
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Runs `attempt`, retrying up to [`MAX_FETCH_ATTEMPTS`] times with
+/// exponential backoff when it fails with an error `is_transient` accepts.
+/// Eclipse and Maven are frequently flaky (see the "Maven loves to be down"
+/// comment in `debugger.rs`), so a single dropped connection shouldn't fail
+/// an entire jdtls/JDK/lombok install. A non-retryable error (e.g. a 404)
+/// returns immediately instead of retrying something that will fail the
+/// same way every time.
+pub fn retry_with_backoff<T>(is_transient: impl Fn(&str) -> bool, mut attempt: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt_number = 1;
+    loop {
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt_number < MAX_FETCH_ATTEMPTS && is_transient(&err) => {
+                std::thread::sleep(BASE_RETRY_DELAY * 2u32.pow(attempt_number - 1));
+                attempt_number += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// [`retry_with_backoff`] specialized for `zed::http_client::fetch`, with
+/// [`is_transient_fetch_error`] as the retry predicate.
+pub fn fetch_with_retry(request: &zed::http_client::HttpRequest) -> Result<zed::http_client::HttpResponse> {
+    retry_with_backoff(is_transient_fetch_error, || {
+        zed::http_client::fetch(request).map_err(|err| format!("failed to fetch {}: {err}", request.url))
+    })
+}
+
+/// Whether a `fetch`/`download_file` error looks like a transient network
+/// hiccup worth retrying, rather than something that will fail the same way
+/// on every attempt. The extension API only surfaces a plain `String`, not a
+/// structured status code, so this matches on the handful of substrings a
+/// non-retryable failure (a 404, a bad request, a malformed URL) tends to
+/// contain; anything else is assumed transient.
+pub(crate) fn is_transient_fetch_error(err: &str) -> bool {
+    const NON_RETRYABLE_MARKERS: &[&str] = &["404", "400", "401", "403", "not found", "invalid url", "malformed"];
+    let lower = err.to_lowercase();
+    !NON_RETRYABLE_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+/// Downloads and extracts `url` into `destination`, first verifying the
+/// response is really an archive of `file_type` rather than an HTML error
+/// page. Used for both jdtls and JDK downloads.
+///
+/// When `expected_sha1`/`expected_sha256` is set, the digest of the
+/// downloaded bytes is checked against it before extraction even starts —
+/// so a truncated or corrupted download fails outright with a clear error
+/// instead of leaving a broken (or silently stale) install behind. Eclipse
+/// publishes `.sha1` sidecars; Corretto publishes `.sha256` ones — callers
+/// pass whichever one their provider has, leaving the other `None`.
+///
+/// Extraction happens directly against the body this function already
+/// fetched and verified above, rather than handing `url` to
+/// `zed::download_file` for a second, independent fetch: the latter would
+/// write and extract whatever bytes that second request actually returned,
+/// which aren't guaranteed to be the same bytes the checksum above just
+/// verified (a mirror swapping the file between requests, a flaky proxy
+/// serving a different response, etc.) — checking one copy and then
+/// installing a different, unchecked one would make the checksum above
+/// theater.
+pub fn download_archive(
+    url: &str,
+    destination: &str,
+    file_type: zed::DownloadedFileType,
+    user_agent: &str,
+    expected_sha1: Option<&str>,
+    expected_sha256: Option<&str>,
+) -> Result<()> {
+    let response = fetch_with_retry(&zed::http_client::HttpRequest {
+        method: zed::http_client::HttpMethod::Get,
+        url: url.to_string(),
+        headers: vec![("User-Agent".to_string(), user_agent.to_string())],
+        body: None,
+        redirect_policy: zed::http_client::RedirectPolicy::FollowAll,
+    })?;
+
+    verify_archive_magic(&response.body, file_type)?;
+
+    if let Some(expected_sha1) = expected_sha1 {
+        verify_sha1_checksum(&response.body, expected_sha1)?;
+    }
+
+    if let Some(expected_sha256) = expected_sha256 {
+        verify_sha256_checksum(&response.body, expected_sha256)?;
+    }
+
+    extract_archive(&response.body, destination, file_type)
+        .map_err(|err| format!("failed to extract archive from {url}: {err}"))
+}
+
+/// Extracts `body` (already fetched and checksum-verified by
+/// [`download_archive`]) into `destination`. Only `GzipTar` is implemented —
+/// the only kind `download_archive` is ever called with today, for both
+/// jdtls and JDK archives — so this doesn't need to pull in a zip-reading
+/// dependency for a code path nothing exercises yet.
+fn extract_archive(body: &[u8], destination: &str, file_type: zed::DownloadedFileType) -> Result<()> {
+    match file_type {
+        zed::DownloadedFileType::GzipTar => {
+            fs::create_dir_all(destination).map_err(|err| format!("failed to create directory {destination}: {err}"))?;
+            tar::Archive::new(flate2::read::GzDecoder::new(body))
+                .unpack(destination)
+                .map_err(|err| format!("failed to unpack tarball into {destination}: {err}"))
+        }
+        other => Err(format!("extracting a {other:?} archive isn't supported")),
+    }
+}
+
+/// Compares the SHA-1 digest of `body` against `expected` (published by
+/// Eclipse as a `.sha1` sidecar file next to each milestone tarball).
+fn verify_sha1_checksum(body: &[u8], expected: &str) -> Result<()> {
+    use sha1::{Digest, Sha1};
+
+    let mut hasher = Sha1::new();
+    hasher.update(body);
+    let actual = hex_encode(&hasher.finalize());
+    let expected = expected.trim().to_lowercase();
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "checksum mismatch: expected sha1 {expected}, got {actual} — the download is likely corrupt"
+        ))
+    }
+}
+
+/// Compares the SHA-256 digest of `body` against `expected` (published by
+/// Corretto as a `.sha256` sidecar file next to each JDK archive).
+fn verify_sha256_checksum(body: &[u8], expected: &str) -> Result<()> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(body);
+    let actual = hex_encode(&hasher.finalize());
+    let expected = expected.trim().to_lowercase();
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "checksum mismatch: expected sha256 {expected}, got {actual} — the download is likely corrupt"
+        ))
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Strips any directory components from `path`, leaving only the final
+/// component. Used to keep filesystem paths (which can embed a username,
+/// e.g. `/home/alice/project`) out of error messages that get pasted into
+/// filed issues verbatim.
+///
+/// Not called yet — no current error message embeds a raw filesystem path
+/// that would need this; kept ready for the next one that does.
+#[allow(dead_code)]
+pub fn redact_to_basename(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Builds a compact `key=value key=value ...` string summarizing the
+/// environment an error happened in — platform, arch, resolved JDK, jdtls
+/// build, update mode — so it can be appended to error messages. Missing
+/// fields (`None`, e.g. the JDK couldn't be resolved before the error
+/// occurred) are reported as `unknown` rather than omitted, so the shape of
+/// a pasted error report is consistent regardless of when it failed.
+pub fn diagnostic_context(fields: &[(&str, Option<String>)]) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{key}={}", value.as_deref().unwrap_or("unknown")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Removes every entry in `dir` except `keep`, used to prune stale
+/// versioned installs after a successful upgrade.
+pub fn remove_all_files_except(dir: &str, keep: &str) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(|err| format!("failed to read directory {dir}: {err}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let file_name = entry.file_name();
+        if file_name.to_string_lossy() == keep {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            fs::remove_dir_all(&path).ok();
+        } else {
+            fs::remove_file(&path).ok();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_html_error_page_as_gzip() {
+        let html = b"<html><body>rate limited</body></html>";
+        assert!(verify_archive_magic(html, zed::DownloadedFileType::GzipTar).is_err());
+    }
+
+    #[test]
+    fn accepts_real_gzip_magic() {
+        let body = [0x1F, 0x8B, 0x08, 0x00];
+        assert!(verify_archive_magic(&body, zed::DownloadedFileType::GzipTar).is_ok());
+    }
+
+    #[test]
+    fn accepts_real_zip_magic() {
+        let body = b"PK\x03\x04";
+        assert!(verify_archive_magic(body, zed::DownloadedFileType::Zip).is_ok());
+    }
+
+    #[test]
+    fn extracts_a_gzip_tar_body_into_destination() {
+        let dir = std::env::temp_dir().join("zed-java-extract-archive-test");
+        let _ = fs::remove_dir_all(&dir);
+        let destination = dir.to_str().unwrap();
+
+        let mut tar_bytes = Vec::new();
+        {
+            let mut builder = tar::Builder::new(&mut tar_bytes);
+            let contents = b"hello from the archive";
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, "greeting.txt", &contents[..]).unwrap();
+            builder.finish().unwrap();
+        }
+        let mut gzipped = Vec::new();
+        {
+            use flate2::write::GzEncoder;
+            use std::io::Write;
+            let mut encoder = GzEncoder::new(&mut gzipped, flate2::Compression::default());
+            encoder.write_all(&tar_bytes).unwrap();
+            encoder.finish().unwrap();
+        }
+
+        extract_archive(&gzipped, destination, zed::DownloadedFileType::GzipTar).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("greeting.txt")).unwrap(), "hello from the archive");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_extracting_an_unsupported_archive_type() {
+        let dir = std::env::temp_dir().join("zed-java-extract-archive-unsupported-test");
+        assert!(extract_archive(b"PK\x03\x04", dir.to_str().unwrap(), zed::DownloadedFileType::Zip).is_err());
+    }
+
+    #[test]
+    fn sha1_checksum_matches_a_known_digest() {
+        // SHA-1 of the empty byte string.
+        assert!(verify_sha1_checksum(b"", "da39a3ee5e6b4b0d3255bfef95601890afd80709").is_ok());
+    }
+
+    #[test]
+    fn sha256_checksum_matches_a_known_digest() {
+        // SHA-256 of the empty byte string.
+        assert!(verify_sha256_checksum(b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855").is_ok());
+    }
+
+    #[test]
+    fn sha256_checksum_is_case_insensitive() {
+        assert!(verify_sha256_checksum(b"", "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855").is_ok());
+    }
+
+    #[test]
+    fn sha256_checksum_mismatch_is_an_error() {
+        assert!(verify_sha256_checksum(b"corrupted bytes", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855").is_err());
+    }
+
+    #[test]
+    fn expands_a_leading_tilde_against_home() {
+        assert_eq!(expand_home_path("~/.sdkman/candidates/java/current", Some("/home/alice")), "/home/alice/.sdkman/candidates/java/current");
+    }
+
+    #[test]
+    fn leaves_paths_without_a_leading_tilde_unchanged() {
+        assert_eq!(expand_home_path("/opt/jdk-21", Some("/home/alice")), "/opt/jdk-21");
+    }
+
+    #[test]
+    fn leaves_a_tilde_path_unchanged_when_home_is_unknown() {
+        assert_eq!(expand_home_path("~/jdk-21", None), "~/jdk-21");
+    }
+
+    #[test]
+    fn accepts_a_file_that_starts_with_the_jar_signature() {
+        let path = std::env::temp_dir().join("zed-java-verify-jar-magic-valid.jar");
+        let path = path.to_str().unwrap();
+        fs::write(path, [0x50, 0x4B, 0x03, 0x04, 0x14, 0x00]).unwrap();
+
+        assert!(verify_jar_magic(path).is_ok());
+        assert!(Path::new(path).exists());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_and_deletes_an_html_error_page_masquerading_as_a_jar() {
+        let path = std::env::temp_dir().join("zed-java-verify-jar-magic-invalid.jar");
+        let path = path.to_str().unwrap();
+        fs::write(path, b"<html><body>404 Not Found</body></html>").unwrap();
+
+        assert!(verify_jar_magic(path).is_err());
+        assert!(!Path::new(path).exists());
+    }
+
+    #[test]
+    fn existing_jar_check_does_not_delete_on_failure() {
+        let path = std::env::temp_dir().join("zed-java-verify-existing-jar-invalid.jar");
+        let path = path.to_str().unwrap();
+        fs::write(path, b"<html><body>404 Not Found</body></html>").unwrap();
+
+        assert!(verify_existing_jar(path).is_err());
+        assert!(Path::new(path).exists());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn existing_jar_check_errors_when_the_path_is_missing() {
+        let path = std::env::temp_dir().join("zed-java-verify-existing-jar-missing.jar");
+        assert!(verify_existing_jar(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn existing_jar_check_accepts_a_real_jar() {
+        let path = std::env::temp_dir().join("zed-java-verify-existing-jar-valid.jar");
+        let path = path.to_str().unwrap();
+        fs::write(path, [0x50, 0x4B, 0x03, 0x04, 0x14, 0x00]).unwrap();
+
+        assert!(verify_existing_jar(path).is_ok());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn create_path_if_not_exists_is_idempotent_across_repeated_installs() {
+        // Mirrors what `lombok::ensure_installed`/`jdtls::ensure_installed`
+        // rely on: the first install creates the directory, and a later
+        // install (e.g. fetching a newer version) must not fail just
+        // because that directory is already there.
+        let path = std::env::temp_dir().join("zed-java-create-path-if-not-exists-test");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_dir(path);
+
+        create_path_if_not_exists(path).unwrap();
+        assert!(Path::new(path).exists());
+        create_path_if_not_exists(path).unwrap();
+
+        fs::remove_dir(path).unwrap();
+    }
+
+    #[test]
+    fn passes_url_through_unchanged_when_no_mirror_configured() {
+        let url = "https://download.eclipse.org/jdtls/milestones/latest.txt";
+        assert_eq!(apply_download_mirror(url, None), url);
+    }
+
+    #[test]
+    fn rewrites_url_under_the_mirror_base_when_configured() {
+        let url = "https://download.eclipse.org/jdtls/milestones/latest.txt";
+        assert_eq!(
+            apply_download_mirror(url, Some("https://nexus.corp.example/repository/raw-proxy")),
+            "https://nexus.corp.example/repository/raw-proxy/download.eclipse.org/jdtls/milestones/latest.txt"
+        );
+    }
+
+    #[test]
+    fn trims_a_trailing_slash_on_the_mirror_base() {
+        let url = "https://corretto.aws/downloads/latest/amazon-corretto-25-x64-linux-jdk.tar.gz";
+        assert_eq!(
+            apply_download_mirror(url, Some("https://nexus.corp.example/repository/raw-proxy/")),
+            "https://nexus.corp.example/repository/raw-proxy/corretto.aws/downloads/latest/amazon-corretto-25-x64-linux-jdk.tar.gz"
+        );
+    }
+
+    #[test]
+    fn sha1_checksum_is_case_insensitive() {
+        assert!(verify_sha1_checksum(b"", "DA39A3EE5E6B4B0D3255BFEF95601890AFD80709").is_ok());
+    }
+
+    #[test]
+    fn retry_with_backoff_retries_transient_failures_until_success() {
+        let mut attempts = 0;
+        let result = retry_with_backoff(
+            |_| true,
+            || {
+                attempts += 1;
+                if attempts < 2 {
+                    Err("connection reset".to_string())
+                } else {
+                    Ok(attempts)
+                }
+            },
+        );
+        assert_eq!(result, Ok(2));
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn retry_with_backoff_gives_up_after_max_attempts() {
+        let mut attempts = 0;
+        let result: Result<()> = retry_with_backoff(
+            |_| true,
+            || {
+                attempts += 1;
+                Err("connection reset".to_string())
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(attempts, MAX_FETCH_ATTEMPTS);
+    }
+
+    #[test]
+    fn retry_with_backoff_does_not_retry_a_non_transient_error() {
+        let mut attempts = 0;
+        let result: Result<()> = retry_with_backoff(
+            |_| false,
+            || {
+                attempts += 1;
+                Err("404 not found".to_string())
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn treats_404_as_non_retryable() {
+        assert!(!is_transient_fetch_error("server responded with 404 Not Found"));
+    }
+
+    #[test]
+    fn treats_connection_reset_as_transient() {
+        assert!(is_transient_fetch_error("connection reset by peer"));
+    }
+
+    #[test]
+    fn sha1_checksum_mismatch_is_an_error() {
+        assert!(verify_sha1_checksum(b"corrupted bytes", "da39a3ee5e6b4b0d3255bfef95601890afd80709").is_err());
+    }
+
+    #[test]
+    fn falls_back_to_default_user_agent() {
+        assert_eq!(resolve_user_agent(None), DEFAULT_USER_AGENT);
+    }
+
+    #[test]
+    fn honors_user_agent_override() {
+        assert_eq!(resolve_user_agent(Some("custom-agent/1.0")), "custom-agent/1.0");
+    }
+
+    #[test]
+    fn pointer_check_absent_when_key_missing() {
+        let value = serde_json::json!({});
+        assert!(matches!(
+            check_pointer_shape(&value, "/runtimes", Value::is_array),
+            PointerCheck::Absent
+        ));
+    }
+
+    #[test]
+    fn pointer_check_ok_when_shape_matches() {
+        let value = serde_json::json!({"runtimes": []});
+        assert!(matches!(
+            check_pointer_shape(&value, "/runtimes", Value::is_array),
+            PointerCheck::Ok
+        ));
+    }
+
+    #[test]
+    fn pointer_check_reports_wrong_type() {
+        let value = serde_json::json!({"runtimes": "not-an-array"});
+        assert!(matches!(
+            check_pointer_shape(&value, "/runtimes", Value::is_array),
+            PointerCheck::WrongType { found: "string" }
+        ));
+    }
+
+    #[test]
+    fn deep_merge_combines_nested_objects() {
+        let mut base = serde_json::json!({"java": {"home": "/opt/jdk", "format": {"enabled": true}}});
+        let overlay = serde_json::json!({"java": {"format": {"tabSize": 4}}});
+        deep_merge(&mut base, &overlay);
+        assert_eq!(
+            base,
+            serde_json::json!({"java": {"home": "/opt/jdk", "format": {"enabled": true, "tabSize": 4}}})
+        );
+    }
+
+    #[test]
+    fn redact_to_basename_strips_leading_directories() {
+        assert_eq!(redact_to_basename("/home/alice/project/.jdtls/data"), "data");
+    }
+
+    #[test]
+    fn redact_to_basename_passes_through_bare_names() {
+        assert_eq!(redact_to_basename("jdtls"), "jdtls");
+    }
+
+    #[test]
+    fn diagnostic_context_joins_fields_and_marks_missing_as_unknown() {
+        assert_eq!(
+            diagnostic_context(&[("platform", Some("linux".to_string())), ("jdk", None)]),
+            "platform=linux jdk=unknown"
+        );
+    }
+
+    #[test]
+    fn deep_merge_overlay_replaces_non_object_values() {
+        let mut base = serde_json::json!({"java": {"home": "/opt/jdk"}});
+        let overlay = serde_json::json!({"java": {"home": "/opt/other-jdk"}});
+        deep_merge(&mut base, &overlay);
+        assert_eq!(base["java"]["home"], "/opt/other-jdk");
+    }
+}